@@ -0,0 +1,574 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use colored::*;
+use serde_json::Value;
+use toml_edit::{DocumentMut, Item};
+use crate::context::CommandContext;
+use crate::output::{CommandOutput, EzError};
+use crate::{ConfigAction, ConfigFormat};
+
+/// One step of a key path such as `servers[2].host`.
+#[derive(Debug)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// JSON and YAML are edited as serde_yaml values, whose mappings keep keys in
+/// file order (serde_json's maps only do with a crate-wide feature).
+type Tree = serde_yaml::Value;
+
+/// Parsed file contents. TOML keeps its own document so comments and layout survive.
+enum Document {
+    Json(Tree),
+    Yaml(Tree),
+    Toml(DocumentMut),
+}
+
+pub fn execute(
+    file: PathBuf,
+    action: ConfigAction,
+    expression: String,
+    format: Option<ConfigFormat>,
+    backup: Option<String>,
+    ctx: &CommandContext,
+) -> Result<CommandOutput, EzError> {
+    let format = format.or_else(|| detect_format(&file)).ok_or_else(|| {
+        EzError::InvalidArgs(format!(
+            "Cannot detect the format of '{}'. Use --format json|toml|yaml", file.display()
+        ))
+    })?;
+
+    let contents = fs::read_to_string(&file).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            EzError::NotFound(format!("Cannot read '{}': {}", file.display(), e))
+        } else {
+            EzError::General(format!("Cannot read '{}': {}", file.display(), e))
+        }
+    })?;
+
+    let mut doc = parse(&contents, format)
+        .map_err(|e| EzError::InvalidArgs(format!("Cannot parse '{}': {}", file.display(), e)))?;
+
+    let (path_str, raw_value) = match action {
+        ConfigAction::Set => match expression.split_once('=') {
+            Some((p, v)) => (p.trim().to_string(), Some(v.to_string())),
+            None => return Err(EzError::InvalidArgs("Expected KEY=VALUE, e.g. server.port=8080".to_string())),
+        },
+        _ => (expression.trim().to_string(), None),
+    };
+    let path = parse_path(&path_str)?;
+
+    let previous = lookup(&doc, &path);
+
+    if let ConfigAction::Get = action {
+        let value = previous.ok_or_else(|| {
+            EzError::NotFound(format!("Key '{}' not found in '{}'", path_str, file.display()))
+        })?;
+        if !ctx.json {
+            match &value {
+                Value::String(s) => println!("{}", s),
+                other => println!("{}", serde_json::to_string_pretty(other).unwrap()),
+            }
+        }
+        return Ok(CommandOutput::new("config-edit", serde_json::json!({
+            "file": file.display().to_string(),
+            "key": path_str,
+            "value": value,
+        })));
+    }
+
+    let new_value = raw_value.map(|raw| parse_value(&raw));
+    match &new_value {
+        Some(value) => set(&mut doc, &path, value)?,
+        None => {
+            if previous.is_none() {
+                return Err(EzError::NotFound(format!("Key '{}' not found in '{}'", path_str, file.display())));
+            }
+            delete(&mut doc, &path)?;
+        }
+    }
+
+    let new_contents = serialize(&doc, &contents)?;
+    let verb = if new_value.is_some() { "Set" } else { "Deleted" };
+    let mut data = serde_json::json!({
+        "file": file.display().to_string(),
+        "key": path_str,
+        "previous": previous,
+        "value": new_value,
+        "changed": new_contents != contents,
+    });
+
+    if ctx.dry_run {
+        let diff = crate::utils::unified_diff(&file, &contents, &new_contents);
+        if !ctx.json {
+            crate::utils::print_diff(&diff);
+            println!("{} Would {} '{}' in {}", "~".dimmed(), verb.to_lowercase(), path_str.yellow(), file.display().to_string().cyan());
+        }
+        data["diff"] = serde_json::json!(diff);
+        return Ok(CommandOutput::new("config-edit", data)
+            .with_metadata(serde_json::json!({ "dry_run": true, "format": format_name(format) })));
+    }
+
+    if new_contents != contents {
+        let backup_path = crate::utils::write_atomic(&file, new_contents.as_bytes(), backup.as_deref()).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::PermissionDenied {
+                EzError::PermissionDenied(format!("Cannot write to '{}': {}", file.display(), e))
+            } else {
+                EzError::General(format!("Cannot write to '{}': {}", file.display(), e))
+            }
+        })?;
+        if let Some(b) = backup_path {
+            data["backup"] = serde_json::json!(b.display().to_string());
+        }
+    }
+
+    if !ctx.json {
+        println!("{} {} '{}' in {}", "✓".green(), verb, path_str.yellow(), file.display().to_string().cyan());
+    }
+
+    Ok(CommandOutput::new("config-edit", data)
+        .with_metadata(serde_json::json!({ "format": format_name(format) })))
+}
+
+fn detect_format(path: &Path) -> Option<ConfigFormat> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "json" => Some(ConfigFormat::Json),
+        "toml" => Some(ConfigFormat::Toml),
+        "yaml" | "yml" => Some(ConfigFormat::Yaml),
+        _ => None,
+    }
+}
+
+fn format_name(format: ConfigFormat) -> &'static str {
+    match format {
+        ConfigFormat::Json => "json",
+        ConfigFormat::Toml => "toml",
+        ConfigFormat::Yaml => "yaml",
+    }
+}
+
+fn parse(contents: &str, format: ConfigFormat) -> Result<Document, String> {
+    match format {
+        ConfigFormat::Json => serde_json::from_str(contents).map(Document::Json).map_err(|e| e.to_string()),
+        ConfigFormat::Yaml => {
+            if contents.trim().is_empty() {
+                return Ok(Document::Yaml(Tree::Mapping(Default::default())));
+            }
+            serde_yaml::from_str(contents).map(Document::Yaml).map_err(|e| e.to_string())
+        }
+        ConfigFormat::Toml => contents.parse::<DocumentMut>().map(Document::Toml).map_err(|e| e.to_string()),
+    }
+}
+
+/// Write the document back out. JSON keeps the original indentation and
+/// key order; YAML keeps key order but loses comments (serde_yaml drops them).
+fn serialize(doc: &Document, original: &str) -> Result<String, EzError> {
+    let mut out = match doc {
+        Document::Toml(d) => return Ok(d.to_string()),
+        Document::Json(v) => {
+            let indent = detect_indent(original);
+            let mut buf = Vec::new();
+            let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+            let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
+            serde::Serialize::serialize(v, &mut ser)
+                .map_err(|e| EzError::General(format!("Cannot write JSON: {}", e)))?;
+            String::from_utf8(buf).unwrap()
+        }
+        Document::Yaml(v) => serde_yaml::to_string(v)
+            .map_err(|e| EzError::General(format!("Cannot write YAML: {}", e)))?,
+    };
+    if original.ends_with('\n') && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Indentation of the first indented line, falling back to two spaces.
+fn detect_indent(contents: &str) -> String {
+    contents
+        .lines()
+        .map(|l| &l[..l.len() - l.trim_start().len()])
+        .find(|ws| !ws.is_empty())
+        .unwrap_or("  ")
+        .to_string()
+}
+
+/// Parse `a.b[2]."dotted.key".c` into segments.
+fn parse_path(path: &str) -> Result<Vec<Segment>, EzError> {
+    let invalid = || EzError::InvalidArgs(format!("Invalid key path '{}'", path));
+    let mut segments = Vec::new();
+    let mut chars = path.chars().peekable();
+
+    while chars.peek().is_some() {
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            let mut key = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => key.push(c),
+                    None => return Err(invalid()),
+                }
+            }
+            segments.push(Segment::Key(key));
+        } else {
+            let mut key = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == '.' || c == '[' {
+                    break;
+                }
+                key.push(c);
+                chars.next();
+            }
+            if !key.is_empty() {
+                segments.push(Segment::Key(key));
+            } else if chars.peek() != Some(&'[') {
+                return Err(invalid());
+            }
+        }
+
+        while chars.peek() == Some(&'[') {
+            chars.next();
+            let mut digits = String::new();
+            loop {
+                match chars.next() {
+                    Some(']') => break,
+                    Some(c) => digits.push(c),
+                    None => return Err(invalid()),
+                }
+            }
+            segments.push(Segment::Index(digits.trim().parse().map_err(|_| invalid())?));
+        }
+
+        match chars.next() {
+            Some('.') if chars.peek().is_some() => {}
+            None => {}
+            _ => return Err(invalid()),
+        }
+    }
+
+    if segments.is_empty() {
+        return Err(invalid());
+    }
+    Ok(segments)
+}
+
+/// Values are read as JSON when they parse (numbers, booleans, arrays,
+/// objects, quoted strings); anything else is taken as a plain string.
+fn parse_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+fn lookup(doc: &Document, path: &[Segment]) -> Option<Value> {
+    match doc {
+        // YAML keys that aren't strings have no JSON form
+        Document::Json(v) | Document::Yaml(v) => tree_get(v, path).and_then(|t| serde_json::to_value(t).ok()),
+        Document::Toml(d) => toml_get(d.as_item(), path).map(toml_to_json),
+    }
+}
+
+fn set(doc: &mut Document, path: &[Segment], value: &Value) -> Result<(), EzError> {
+    match doc {
+        Document::Json(v) | Document::Yaml(v) => {
+            let value = serde_yaml::to_value(value).map_err(|e| EzError::InvalidArgs(format!("Invalid value: {}", e)))?;
+            tree_set(v, path, value)
+        }
+        Document::Toml(d) => toml_set(d.as_item_mut(), path, value),
+    }
+}
+
+fn delete(doc: &mut Document, path: &[Segment]) -> Result<(), EzError> {
+    let (last, parents) = path.split_last().unwrap();
+    let removed = match doc {
+        Document::Json(v) | Document::Yaml(v) => match (tree_get_mut(v, parents), last) {
+            (Some(Tree::Mapping(map)), Segment::Key(k)) => map.shift_remove(k.as_str()).is_some(),
+            (Some(Tree::Sequence(arr)), Segment::Index(i)) if *i < arr.len() => {
+                arr.remove(*i);
+                true
+            }
+            _ => false,
+        },
+        Document::Toml(d) => {
+            let parent = toml_get_mut(d.as_item_mut(), parents);
+            match (parent, last) {
+                (Some(item), Segment::Key(k)) => item.as_table_like_mut().and_then(|t| t.remove(k)).is_some(),
+                (Some(item), Segment::Index(i)) => {
+                    if let Some(arr) = item.as_array_mut().filter(|a| *i < a.len()) {
+                        arr.remove(*i);
+                        true
+                    } else if let Some(aot) = item.as_array_of_tables_mut().filter(|a| *i < a.len()) {
+                        aot.remove(*i);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                _ => false,
+            }
+        }
+    };
+
+    if removed {
+        Ok(())
+    } else {
+        Err(EzError::NotFound("Key not found".to_string()))
+    }
+}
+
+fn tree_get<'a>(mut value: &'a Tree, path: &[Segment]) -> Option<&'a Tree> {
+    for seg in path {
+        value = match seg {
+            Segment::Key(k) => value.as_mapping()?.get(k.as_str())?,
+            Segment::Index(i) => value.as_sequence()?.get(*i)?,
+        };
+    }
+    Some(value)
+}
+
+fn tree_get_mut<'a>(mut value: &'a mut Tree, path: &[Segment]) -> Option<&'a mut Tree> {
+    for seg in path {
+        value = match seg {
+            Segment::Key(k) => value.as_mapping_mut()?.get_mut(k.as_str())?,
+            Segment::Index(i) => value.as_sequence_mut()?.get_mut(*i)?,
+        };
+    }
+    Some(value)
+}
+
+/// Set a value, creating missing objects/arrays along the way. An index equal
+/// to the array length appends.
+fn tree_set(value: &mut Tree, path: &[Segment], new: Tree) -> Result<(), EzError> {
+    let (seg, rest) = path.split_first().unwrap();
+
+    let slot = match seg {
+        Segment::Key(k) => {
+            if value.is_null() {
+                *value = Tree::Mapping(Default::default());
+            }
+            let map = value.as_mapping_mut().ok_or_else(|| {
+                EzError::InvalidArgs(format!("Cannot use key '{}' on a non-object value", k))
+            })?;
+            map.entry(Tree::String(k.clone())).or_insert(Tree::Null)
+        }
+        Segment::Index(i) => {
+            if value.is_null() {
+                *value = Tree::Sequence(Vec::new());
+            }
+            let arr = value.as_sequence_mut().ok_or_else(|| {
+                EzError::InvalidArgs(format!("Cannot use index [{}] on a non-array value", i))
+            })?;
+            let len = arr.len();
+            if *i == len {
+                arr.push(Tree::Null);
+            }
+            arr.get_mut(*i).ok_or_else(|| {
+                EzError::InvalidArgs(format!("Index [{}] is out of range (length {})", i, len))
+            })?
+        }
+    };
+
+    if rest.is_empty() {
+        *slot = new;
+        Ok(())
+    } else {
+        tree_set(slot, rest, new)
+    }
+}
+
+fn toml_get<'a>(mut item: &'a Item, path: &[Segment]) -> Option<&'a Item> {
+    for seg in path {
+        item = match seg {
+            Segment::Key(k) => item.get(k.as_str())?,
+            Segment::Index(i) => item.get(*i)?,
+        };
+    }
+    Some(item)
+}
+
+fn toml_get_mut<'a>(mut item: &'a mut Item, path: &[Segment]) -> Option<&'a mut Item> {
+    for seg in path {
+        item = match seg {
+            // `Item::get_mut` inserts missing keys, so check first
+            Segment::Key(k) if item.get(k.as_str()).is_some() => item.get_mut(k.as_str())?,
+            Segment::Index(i) => item.get_mut(*i)?,
+            _ => return None,
+        };
+    }
+    Some(item)
+}
+
+fn toml_set(item: &mut Item, path: &[Segment], new: &Value) -> Result<(), EzError> {
+    let (seg, rest) = path.split_first().unwrap();
+
+    if rest.is_empty() {
+        let mut value = json_to_toml(new)?;
+        match seg {
+            Segment::Key(k) => {
+                let table = item.as_table_like_mut().ok_or_else(|| {
+                    EzError::InvalidArgs(format!("Cannot use key '{}' on a non-table value", k))
+                })?;
+                // Keep any comment attached to the old value
+                if let Some(old) = table.get(k).and_then(|i| i.as_value()) {
+                    *value.decor_mut() = old.decor().clone();
+                }
+                table.insert(k, Item::Value(value));
+            }
+            Segment::Index(i) => {
+                if let Some(arr) = item.as_array_mut() {
+                    if *i < arr.len() {
+                        let decor = arr.get(*i).unwrap().decor().clone();
+                        *value.decor_mut() = decor;
+                        arr.replace(*i, value);
+                    } else if *i == arr.len() {
+                        arr.push(value);
+                    } else {
+                        return Err(EzError::InvalidArgs(format!("Index [{}] is out of range (length {})", i, arr.len())));
+                    }
+                } else if let Some(aot) = item.as_array_of_tables_mut() {
+                    let table = match value {
+                        toml_edit::Value::InlineTable(t) => t.into_table(),
+                        _ => return Err(EzError::InvalidArgs(format!("Entry [{}] must be an object", i))),
+                    };
+                    if *i < aot.len() {
+                        *aot.get_mut(*i).unwrap() = table;
+                    } else if *i == aot.len() {
+                        aot.push(table);
+                    } else {
+                        return Err(EzError::InvalidArgs(format!("Index [{}] is out of range (length {})", i, aot.len())));
+                    }
+                } else {
+                    return Err(EzError::InvalidArgs(format!("Cannot use index [{}] on a non-array value", i)));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let child = match seg {
+        Segment::Key(k) => {
+            let is_table = item.is_table();
+            let table = item.as_table_like_mut().ok_or_else(|| {
+                EzError::InvalidArgs(format!("Cannot use key '{}' on a non-table value", k))
+            })?;
+            if table.get(k).is_none() {
+                // Under a [table] create a real (implicit) table; inside inline values stay inline
+                let fresh = match rest[0] {
+                    Segment::Index(_) => toml_edit::value(toml_edit::Array::new()),
+                    Segment::Key(_) if is_table => {
+                        let mut t = toml_edit::Table::new();
+                        t.set_implicit(true);
+                        Item::Table(t)
+                    }
+                    Segment::Key(_) => toml_edit::value(toml_edit::InlineTable::new()),
+                };
+                table.insert(k, fresh);
+            }
+            table.get_mut(k).unwrap()
+        }
+        Segment::Index(i) => item.get_mut(*i).ok_or_else(|| {
+            EzError::InvalidArgs(format!("Index [{}] is out of range", i))
+        })?,
+    };
+
+    toml_set(child, rest, new)
+}
+
+fn json_to_toml(value: &Value) -> Result<toml_edit::Value, EzError> {
+    Ok(match value {
+        Value::Null => return Err(EzError::InvalidArgs("TOML has no null value; use delete instead".to_string())),
+        Value::Bool(b) => (*b).into(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n.as_f64().unwrap_or(0.0).into(),
+        },
+        Value::String(s) => s.as_str().into(),
+        Value::Array(items) => {
+            let mut arr = toml_edit::Array::new();
+            for v in items {
+                arr.push(json_to_toml(v)?);
+            }
+            toml_edit::Value::Array(arr)
+        }
+        Value::Object(map) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (k, v) in map {
+                table.insert(k, json_to_toml(v)?);
+            }
+            toml_edit::Value::InlineTable(table)
+        }
+    })
+}
+
+fn toml_to_json(item: &Item) -> Value {
+    match item {
+        Item::None => Value::Null,
+        Item::Value(v) => toml_value_to_json(v),
+        Item::Table(t) => Value::Object(t.iter().map(|(k, v)| (k.to_string(), toml_to_json(v))).collect()),
+        Item::ArrayOfTables(aot) => Value::Array(
+            aot.iter()
+                .map(|t| Value::Object(t.iter().map(|(k, v)| (k.to_string(), toml_to_json(v))).collect()))
+                .collect(),
+        ),
+    }
+}
+
+fn toml_value_to_json(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(s) => Value::String(s.value().clone()),
+        toml_edit::Value::Integer(i) => serde_json::json!(*i.value()),
+        toml_edit::Value::Float(f) => serde_json::json!(*f.value()),
+        toml_edit::Value::Boolean(b) => Value::Bool(*b.value()),
+        toml_edit::Value::Datetime(d) => Value::String(d.value().to_string()),
+        toml_edit::Value::Array(a) => Value::Array(a.iter().map(toml_value_to_json).collect()),
+        toml_edit::Value::InlineTable(t) => Value::Object(
+            t.iter().map(|(k, v)| (k.to_string(), toml_value_to_json(v))).collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(contents: &str, format: ConfigFormat, path: &str, value: Option<&str>) -> String {
+        let mut doc = parse(contents, format).unwrap();
+        let path = parse_path(path).ok().unwrap();
+        match value {
+            Some(raw) => assert!(set(&mut doc, &path, &parse_value(raw)).is_ok()),
+            None => assert!(delete(&mut doc, &path).is_ok()),
+        }
+        serialize(&doc, contents).ok().unwrap()
+    }
+
+    #[test]
+    fn key_paths_are_parsed() {
+        let path = parse_path(r#"servers[2]."dotted.key".port"#).ok().unwrap();
+        assert_eq!(format!("{:?}", path), r#"[Key("servers"), Index(2), Key("dotted.key"), Key("port")]"#);
+        for bad in ["", "a..b", "a[x]", "a[1", "\"open"] {
+            assert!(parse_path(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn json_keeps_key_order_and_indent() {
+        let json = "{\n    \"zeta\": 1,\n    \"alpha\": {\"b\": 2, \"a\": 3}\n}\n";
+        assert_eq!(
+            edit(json, ConfigFormat::Json, "alpha.c", Some("true")),
+            "{\n    \"zeta\": 1,\n    \"alpha\": {\n        \"b\": 2,\n        \"a\": 3,\n        \"c\": true\n    }\n}\n",
+        );
+        assert_eq!(edit(json, ConfigFormat::Json, "alpha.b", None), "{\n    \"zeta\": 1,\n    \"alpha\": {\n        \"a\": 3\n    }\n}\n");
+    }
+
+    #[test]
+    fn yaml_keeps_key_order() {
+        assert_eq!(edit("z: 1\na: [x]\n", ConfigFormat::Yaml, "a[1]", Some("y")), "z: 1\na:\n- x\n- y\n");
+    }
+
+    #[test]
+    fn toml_keeps_comments() {
+        let toml = "# settings\n[server]\nport = 80 # default\nhost = \"x\"\n";
+        assert_eq!(edit(toml, ConfigFormat::Toml, "server.port", Some("8080")), "# settings\n[server]\nport = 8080 # default\nhost = \"x\"\n");
+    }
+}
@@ -1,7 +1,10 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use colored::*;
-use std::io::Write;
+use std::io::{Read, Write};
+use filetime::FileTime;
+use indicatif::ProgressBar;
+use sha2::{Digest, Sha256};
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
 
@@ -86,3 +89,132 @@ fn copy_dir(from: &PathBuf, to: &PathBuf, progress: bool, ctx: &CommandContext)
 
     Ok(())
 }
+
+/// Totals reported by `copy_verified`.
+pub struct CopyStats {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Copy a file, symlink or whole folder to `to`, fsyncing every file and
+/// re-reading it to confirm its SHA-256 matches the source. Permissions and
+/// modification times are carried over. `to` must not exist yet.
+pub fn copy_verified(from: &Path, to: &Path, pb: Option<&ProgressBar>) -> Result<CopyStats, EzError> {
+    let mut stats = CopyStats { files: 0, bytes: 0 };
+    copy_verified_into(from, to, pb, &mut stats)?;
+    Ok(stats)
+}
+
+fn copy_verified_into(from: &Path, to: &Path, pb: Option<&ProgressBar>, stats: &mut CopyStats) -> Result<(), EzError> {
+    let metadata = fs::symlink_metadata(from).map_err(|e| copy_error(from, e))?;
+
+    if metadata.file_type().is_symlink() {
+        let target = fs::read_link(from).map_err(|e| copy_error(from, e))?;
+        std::os::unix::fs::symlink(&target, to).map_err(|e| copy_error(to, e))?;
+        return Ok(());
+    }
+
+    if metadata.is_dir() {
+        fs::create_dir(to).map_err(|e| copy_error(to, e))?;
+        for entry in fs::read_dir(from).map_err(|e| copy_error(from, e))? {
+            let entry = entry.map_err(|e| copy_error(from, e))?;
+            copy_verified_into(&entry.path(), &to.join(entry.file_name()), pb, stats)?;
+        }
+        // Applied last so a read-only source folder doesn't block its own children
+        fs::set_permissions(to, metadata.permissions()).map_err(|e| copy_error(to, e))?;
+        filetime::set_file_mtime(to, FileTime::from_last_modification_time(&metadata))
+            .map_err(|e| copy_error(to, e))?;
+        return Ok(());
+    }
+
+    let (source_hash, bytes) = copy_file_hashed(from, to, pb)?;
+    let dest_hash = crate::utils::sha256_file(to).map_err(|e| copy_error(to, e))?;
+    if source_hash != dest_hash {
+        return Err(EzError::General(format!(
+            "Verification failed for '{}': checksum of the copy does not match the original",
+            from.display()
+        )));
+    }
+
+    fs::set_permissions(to, metadata.permissions()).map_err(|e| copy_error(to, e))?;
+    filetime::set_file_mtime(to, FileTime::from_last_modification_time(&metadata))
+        .map_err(|e| copy_error(to, e))?;
+
+    stats.files += 1;
+    stats.bytes += bytes;
+    Ok(())
+}
+
+/// Stream `from` into a new file at `to`, hashing as we go, and fsync the result.
+fn copy_file_hashed(from: &Path, to: &Path, pb: Option<&ProgressBar>) -> Result<(String, u64), EzError> {
+    let mut reader = fs::File::open(from).map_err(|e| copy_error(from, e))?;
+    let mut writer = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(to)
+        .map_err(|e| copy_error(to, e))?;
+
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 65536];
+    let mut bytes: u64 = 0;
+    loop {
+        let n = reader.read(&mut buffer).map_err(|e| copy_error(from, e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        writer.write_all(&buffer[..n]).map_err(|e| copy_error(to, e))?;
+        bytes += n as u64;
+        if let Some(pb) = pb {
+            pb.inc(n as u64);
+        }
+    }
+    writer.sync_all().map_err(|e| copy_error(to, e))?;
+
+    Ok((format!("{:x}", hasher.finalize()), bytes))
+}
+
+fn copy_error(path: &Path, e: std::io::Error) -> EzError {
+    if e.kind() == std::io::ErrorKind::PermissionDenied {
+        EzError::PermissionDenied(format!("Copy failed for '{}': {}", path.display(), e))
+    } else {
+        EzError::General(format!("Copy failed for '{}': {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn copies_folders_with_modes_times_and_links() {
+        let tmp = tempfile::tempdir().unwrap();
+        let from = tmp.path().join("src");
+        fs::create_dir_all(from.join("sub")).unwrap();
+        fs::write(from.join("run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(from.join("run.sh"), fs::Permissions::from_mode(0o750)).unwrap();
+        fs::write(from.join("sub/data.bin"), vec![7u8; 100_000]).unwrap();
+        filetime::set_file_mtime(from.join("sub/data.bin"), FileTime::from_unix_time(1_000_000, 0)).unwrap();
+        std::os::unix::fs::symlink("sub/data.bin", from.join("link")).unwrap();
+
+        let to = tmp.path().join("dest");
+        let Ok(stats) = copy_verified(&from, &to, None) else { panic!("copy failed") };
+        assert_eq!((stats.files, stats.bytes), (2, 100_010));
+        assert_eq!(fs::read(to.join("sub/data.bin")).unwrap(), vec![7u8; 100_000]);
+        assert_eq!(fs::metadata(to.join("run.sh")).unwrap().permissions().mode() & 0o777, 0o750);
+        let mtime = FileTime::from_last_modification_time(&fs::metadata(to.join("sub/data.bin")).unwrap());
+        assert_eq!(mtime.unix_seconds(), 1_000_000);
+        assert_eq!(fs::read_link(to.join("link")).unwrap(), PathBuf::from("sub/data.bin"));
+    }
+
+    #[test]
+    fn never_copies_over_an_existing_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("a"), "new").unwrap();
+        fs::write(tmp.path().join("b"), "keep").unwrap();
+
+        assert!(copy_verified(&tmp.path().join("a"), &tmp.path().join("b"), None).is_err());
+        assert_eq!(fs::read_to_string(tmp.path().join("b")).unwrap(), "keep");
+    }
+}
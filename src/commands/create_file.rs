@@ -1,41 +1,321 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
 use colored::*;
+use filetime::FileTime;
+use regex::Regex;
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
 
-pub fn execute(paths: Vec<PathBuf>, if_not_exists: bool, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    paths: Vec<PathBuf>,
+    if_not_exists: bool,
+    template: Option<String>,
+    content: Option<String>,
+    time: Option<String>,
+    reference: Option<PathBuf>,
+    mode: Option<String>,
+    ctx: &CommandContext,
+) -> Result<CommandOutput, EzError> {
+    let template_text = match &template {
+        Some(name) => Some(load_template(name)?),
+        None => None,
+    };
+    let content = match content.as_deref() {
+        Some("-") => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)
+                .map_err(|e| EzError::General(format!("Cannot read stdin: {}", e)))?;
+            Some(input)
+        }
+        _ => content,
+    };
+    let mode = mode.as_deref().map(parse_mode).transpose()?;
+    let times = match (&time, &reference) {
+        (Some(t), _) => {
+            let t = crate::utils::parse_time(t)?;
+            let ft = FileTime::from_unix_time(t.timestamp(), t.timestamp_subsec_nanos());
+            Some((ft, ft))
+        }
+        (None, Some(r)) => {
+            let meta = fs::metadata(r).map_err(|e| {
+                EzError::NotFound(format!("Cannot read reference file '{}': {}", r.display(), e))
+            })?;
+            Some((FileTime::from_last_access_time(&meta), FileTime::from_last_modification_time(&meta)))
+        }
+        (None, None) => None,
+    };
+
     let mut created = Vec::new();
     let mut skipped = Vec::new();
+    let mut touched = Vec::new();
 
+    let has_contents = content.is_some() || template_text.is_some();
     for path in paths {
-        if if_not_exists && path.exists() {
+        let exists = path.exists();
+        if exists && if_not_exists && times.is_none() {
             skipped.push(path.display().to_string());
             if !ctx.json {
                 println!("{} Skipped '{}' (already exists)", "~".dimmed(), path.display());
             }
             continue;
         }
+        if exists && (if_not_exists || !has_contents) {
+            // Like `touch`: an existing file keeps its contents and only gets new times (and mode)
+            if !ctx.dry_run {
+                let (atime, mtime) = times.unwrap_or_else(|| {
+                    let now = FileTime::now();
+                    (now, now)
+                });
+                if let Some(m) = mode {
+                    set_mode(&path, m)?;
+                }
+                filetime::set_file_times(&path, atime, mtime)
+                    .map_err(|e| EzError::General(format!("Cannot set times on '{}': {}", path.display(), e)))?;
+            }
+            touched.push(path.display().to_string());
+            if !ctx.json {
+                let what = if mode.is_some() { "times and mode" } else { "times" };
+                if ctx.dry_run {
+                    println!("{} Would update {} on '{}'", "~".dimmed(), what, path.display());
+                } else {
+                    println!("{} Updated {} on '{}'", "✓".green(), what, path.display());
+                }
+            }
+            continue;
+        }
+
+        if ctx.dry_run {
+            created.push(path.display().to_string());
+            if !ctx.json {
+                println!("{} Would create file '{}'", "~".dimmed(), path.display());
+            }
+            continue;
+        }
 
-        fs::File::create(&path).map_err(|e| {
+        let contents = match (&content, &template_text) {
+            (Some(c), _) => Some(c.clone()),
+            (None, Some(t)) => Some(render_template(t, &path)),
+            (None, None) => None,
+        };
+        let contents = contents.as_deref().map(str::as_bytes);
+        let written = if exists {
+            replace_file(&path, contents.unwrap_or_default(), mode, times)
+        } else {
+            write_file(&path, contents, mode, times)
+        };
+        written.map_err(|e| {
+            let verb = if exists { "write to" } else { "create file" };
             if e.kind() == std::io::ErrorKind::PermissionDenied {
-                EzError::PermissionDenied(format!("Cannot create file '{}': {}", path.display(), e))
+                EzError::PermissionDenied(format!("Cannot {} '{}': {}", verb, path.display(), e))
             } else {
-                EzError::General(format!("Cannot create file '{}': {}", path.display(), e))
+                EzError::General(format!("Cannot {} '{}': {}", verb, path.display(), e))
             }
         })?;
         created.push(path.display().to_string());
         if !ctx.json {
-            println!("{} Created file '{}'", "✓".green(), path.display());
+            let verb = if exists { "Replaced contents of" } else { "Created file" };
+            println!("{} {} '{}'", "✓".green(), verb, path.display());
         }
     }
 
     let mut output = CommandOutput::new("create-file", serde_json::json!({
         "created": created,
         "skipped": skipped,
+        "touched": touched,
     }));
+    let mut metadata = serde_json::Map::new();
     if if_not_exists && !skipped.is_empty() {
-        output.metadata = Some(serde_json::json!({ "skipped": true }));
+        metadata.insert("skipped".to_string(), serde_json::json!(true));
+    }
+    if ctx.dry_run {
+        metadata.insert("dry_run".to_string(), serde_json::json!(true));
+    }
+    if let Some(name) = template {
+        metadata.insert("template".to_string(), serde_json::json!(name));
+    }
+    if let Some(m) = mode {
+        metadata.insert("mode".to_string(), serde_json::json!(format!("{:04o}", m)));
+    }
+    if let Some((_, mtime)) = times {
+        if let Some(t) = chrono::DateTime::from_timestamp(mtime.unix_seconds(), mtime.nanoseconds()) {
+            metadata.insert("mtime".to_string(), serde_json::json!(t.with_timezone(&chrono::Local).to_rfc3339()));
+        }
+    }
+    if !metadata.is_empty() {
+        output.metadata = Some(serde_json::Value::Object(metadata));
     }
     Ok(output)
 }
+
+/// Create the file with its final mode already in place, so it is never readable
+/// with looser permissions than asked for, then set its times last.
+fn write_file(path: &Path, contents: Option<&[u8]>, mode: Option<u32>, times: Option<(FileTime, FileTime)>) -> std::io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode.unwrap_or(0o666))
+        .open(path)?;
+    if let Some(m) = mode {
+        // The umask narrows the mode given to open(); apply the exact bits on the handle
+        file.set_permissions(fs::Permissions::from_mode(m))?;
+    }
+    if let Some(contents) = contents {
+        file.write_all(contents)?;
+    }
+    if let Some((atime, mtime)) = times {
+        filetime::set_file_handle_times(&file, Some(atime), Some(mtime))?;
+    }
+    Ok(())
+}
+
+/// Swap in new contents for an existing file atomically. A new mode goes on the
+/// original first, so `write_atomic` carries it over to the replacement.
+fn replace_file(path: &Path, contents: &[u8], mode: Option<u32>, times: Option<(FileTime, FileTime)>) -> std::io::Result<()> {
+    if let Some(m) = mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(m))?;
+    }
+    crate::utils::write_atomic(path, contents, None)?;
+    if let Some((atime, mtime)) = times {
+        filetime::set_file_times(path, atime, mtime)?;
+    }
+    Ok(())
+}
+
+fn set_mode(path: &Path, mode: u32) -> Result<(), EzError> {
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            EzError::PermissionDenied(format!("Cannot change mode of '{}': {}", path.display(), e))
+        } else {
+            EzError::General(format!("Cannot change mode of '{}': {}", path.display(), e))
+        }
+    })
+}
+
+/// Parse an octal mode such as `644`, `0755` or `0o600`.
+fn parse_mode(mode: &str) -> Result<u32, EzError> {
+    let digits = mode.trim_start_matches("0o");
+    u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|m| *m <= 0o7777)
+        .ok_or_else(|| EzError::InvalidArgs(format!("Invalid mode '{}'. Use octal, e.g. 644 or 0755", mode)))
+}
+
+/// Where user templates live: `$XDG_CONFIG_HOME/ez/templates`, else `~/.config/ez/templates`.
+pub fn templates_dir() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(config.join("ez").join("templates"))
+}
+
+/// Load a template by name. `python-cli` matches `python-cli` or `python-cli.<ext>`.
+pub fn load_template(name: &str) -> Result<String, EzError> {
+    let dir = templates_dir()
+        .ok_or_else(|| EzError::NotFound("Cannot locate the templates folder (HOME is not set)".to_string()))?;
+
+    let mut available = Vec::new();
+    if let Ok(entries) = fs::read_dir(&dir) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            if file_name == name || stem == name {
+                return fs::read_to_string(&path)
+                    .map_err(|e| EzError::General(format!("Cannot read template '{}': {}", path.display(), e)));
+            }
+            available.push(stem);
+        }
+    }
+
+    available.sort();
+    let hint = if available.is_empty() {
+        format!("No templates found in {}", dir.display())
+    } else {
+        format!("Available: {}", available.join(", "))
+    };
+    Err(EzError::NotFound(format!("Template '{}' not found. {}", name, hint)))
+}
+
+/// Fill in `{{name}}` (file name without extension), `{{filename}}`, `{{date}}`
+/// and `{{year}}`. Unknown placeholders are left as they are.
+pub fn render_template(template: &str, path: &Path) -> String {
+    let re = Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap();
+    let now = chrono::Local::now();
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let file_name = path.file_name().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+
+    re.replace_all(template, |caps: &regex::Captures| {
+        match &caps[1] {
+            "name" => stem.clone(),
+            "filename" => file_name.clone(),
+            "date" => now.format("%Y-%m-%d").to_string(),
+            "year" => now.format("%Y").to_string(),
+            _ => caps[0].to_string(),
+        }
+    }).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(path: &Path, content: Option<&str>, mode: Option<&str>) {
+        let ctx = CommandContext { json: true, yes: true, dry_run: false, is_tty: false, is_stdin_tty: false };
+        let result = execute(vec![path.to_path_buf()], false, None, content.map(String::from), None, None, mode.map(String::from), &ctx);
+        assert!(result.is_ok());
+    }
+
+    fn mode_of(path: &Path) -> u32 {
+        fs::metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    #[test]
+    fn touching_keeps_contents_but_applies_the_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "keep me").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+
+        run(&file, None, Some("600"));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "keep me");
+        assert_eq!(mode_of(&file), 0o600);
+    }
+
+    #[test]
+    fn new_contents_replace_the_file_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "a much longer old text").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o640)).unwrap();
+
+        run(&file, Some("new"), None);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+        assert_eq!(mode_of(&file), 0o640);
+        run(&file, Some("newer"), Some("600"));
+        assert_eq!(mode_of(&file), 0o600);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn new_files_get_the_exact_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("secret.txt");
+        run(&file, Some("x"), Some("0o604"));
+        assert_eq!(mode_of(&file), 0o604);
+    }
+
+    #[test]
+    fn modes_are_octal() {
+        assert_eq!(parse_mode("644").ok(), Some(0o644));
+        assert_eq!(parse_mode("0755").ok(), Some(0o755));
+        assert!(parse_mode("888").is_err());
+        assert!(parse_mode("17777").is_err());
+    }
+}
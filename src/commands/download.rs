@@ -1,7 +1,6 @@
 use std::fs::File;
 use std::io::{Write, Read};
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
 
@@ -28,13 +27,7 @@ pub fn execute(url: String, save: Option<String>, progress: bool, ctx: &CommandC
     let mut reader = response.into_reader();
 
     if progress && !ctx.json && total_size > 0 {
-        let pb = ProgressBar::new(total_size);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-                .unwrap()
-                .progress_chars("#>-"),
-        );
+        let pb = crate::utils::byte_progress_bar(total_size);
 
         let mut buffer = [0; 8192];
         loop {
@@ -0,0 +1,349 @@
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use colored::*;
+use regex::Regex;
+use crate::context::CommandContext;
+use crate::output::{CommandOutput, EzError};
+
+enum Operation {
+    Hash,
+    InsertAfter(usize),
+    InsertAfterMatch(Regex),
+    DeleteLines(usize, usize),
+    Between(String, String),
+    AppendIfMissing(String),
+}
+
+impl Operation {
+    fn name(&self) -> &'static str {
+        match self {
+            Operation::Hash => "hash",
+            Operation::InsertAfter(_) => "insert-after",
+            Operation::InsertAfterMatch(_) => "insert-after-match",
+            Operation::DeleteLines(..) => "delete-lines",
+            Operation::Between(..) => "between",
+            Operation::AppendIfMissing(_) => "append-if-missing",
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    file: PathBuf,
+    insert_after: Option<usize>,
+    insert_after_match: Option<String>,
+    delete_lines: Option<String>,
+    between: Option<Vec<String>>,
+    append_if_missing: Option<String>,
+    text: Option<String>,
+    expect_hash: Option<String>,
+    backup: Option<String>,
+    ctx: &CommandContext,
+) -> Result<CommandOutput, EzError> {
+    let op = if let Some(n) = insert_after {
+        Operation::InsertAfter(n)
+    } else if let Some(pattern) = insert_after_match {
+        Operation::InsertAfterMatch(Regex::new(&pattern)
+            .map_err(|e| EzError::InvalidArgs(format!("Invalid pattern: {}", e)))?)
+    } else if let Some(range) = delete_lines {
+        let (start, end) = parse_range(&range)?;
+        Operation::DeleteLines(start, end)
+    } else if let Some(markers) = between {
+        Operation::Between(markers[0].clone(), markers[1].clone())
+    } else if let Some(line) = append_if_missing {
+        Operation::AppendIfMissing(line)
+    } else {
+        Operation::Hash
+    };
+
+    let contents = fs::read_to_string(&file).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            EzError::NotFound(format!("Cannot read '{}': {}", file.display(), e))
+        } else if e.kind() == std::io::ErrorKind::PermissionDenied {
+            EzError::PermissionDenied(format!("Cannot read '{}': {}", file.display(), e))
+        } else {
+            EzError::General(format!("Cannot read '{}': {}", file.display(), e))
+        }
+    })?;
+    let hash_before = crate::utils::sha256_bytes(contents.as_bytes());
+
+    if let Some(expected) = &expect_hash {
+        if !expected.eq_ignore_ascii_case(&hash_before) {
+            return Err(EzError::General(format!(
+                "'{}' has changed since it was read (expected hash {}, found {}). Re-read it and try again",
+                file.display(), expected, hash_before
+            )));
+        }
+    }
+
+    if let Operation::Hash = op {
+        if !ctx.json {
+            println!("{}  {}", hash_before, file.display());
+        }
+        return Ok(CommandOutput::new("edit", serde_json::json!({
+            "file": file.display().to_string(),
+            "hash": hash_before,
+        })));
+    }
+
+    let newline = if contents.contains("\r\n") { "\r\n" } else { "\n" };
+    let needs_text = matches!(op, Operation::InsertAfter(_) | Operation::InsertAfterMatch(_) | Operation::Between(..));
+    let block = if needs_text {
+        let text = match text {
+            Some(t) => t,
+            None if !ctx.is_stdin_tty => {
+                let mut input = String::new();
+                std::io::stdin().read_to_string(&mut input)
+                    .map_err(|e| EzError::General(format!("Cannot read stdin: {}", e)))?;
+                input
+            }
+            None => return Err(EzError::InvalidArgs(format!("--{} needs --text (or text piped on stdin)", op.name()))),
+        };
+        to_block(&text, newline)
+    } else {
+        String::new()
+    };
+
+    let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+    let new_contents = apply(&op, &lines, &contents, &block, newline, &file)?;
+    let changed = new_contents != contents;
+    let hash_after = crate::utils::sha256_bytes(new_contents.as_bytes());
+
+    let mut data = serde_json::json!({
+        "file": file.display().to_string(),
+        "operation": op.name(),
+        "changed": changed,
+        "hash_before": hash_before,
+        "hash_after": hash_after,
+    });
+
+    if ctx.dry_run {
+        let diff = crate::utils::unified_diff(&file, &contents, &new_contents);
+        if !ctx.json {
+            crate::utils::print_diff(&diff);
+            if !changed {
+                println!("{} Nothing to change in {}", "~".dimmed(), file.display().to_string().cyan());
+            }
+        }
+        data["diff"] = serde_json::json!(diff);
+        return Ok(CommandOutput::new("edit", data).with_metadata(serde_json::json!({ "dry_run": true })));
+    }
+
+    if !changed {
+        if !ctx.json {
+            println!("{} Nothing to change in {}", "~".dimmed(), file.display().to_string().cyan());
+        }
+        return Ok(CommandOutput::new("edit", data));
+    }
+
+    let backup_path = crate::utils::write_atomic(&file, new_contents.as_bytes(), backup.as_deref()).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            EzError::PermissionDenied(format!("Cannot write to '{}': {}", file.display(), e))
+        } else {
+            EzError::General(format!("Cannot write to '{}': {}", file.display(), e))
+        }
+    })?;
+    if let Some(b) = backup_path {
+        data["backup"] = serde_json::json!(b.display().to_string());
+    }
+
+    if !ctx.json {
+        println!("{} Edited {} ({})", "✓".green(), file.display().to_string().cyan(), op.name());
+    }
+
+    Ok(CommandOutput::new("edit", data))
+}
+
+fn apply(op: &Operation, lines: &[&str], contents: &str, block: &str, newline: &str, file: &std::path::Path) -> Result<String, EzError> {
+    let mut out = String::with_capacity(contents.len() + block.len());
+
+    match op {
+        Operation::Hash => out.push_str(contents),
+        Operation::InsertAfter(n) => {
+            if *n > lines.len() {
+                return Err(EzError::InvalidArgs(format!(
+                    "Line {} is past the end of '{}' ({} lines)", n, file.display(), lines.len()
+                )));
+            }
+            push_lines(&mut out, &lines[..*n], newline);
+            push_lines(&mut out, &[block], newline);
+            push_lines(&mut out, &lines[*n..], newline);
+        }
+        Operation::InsertAfterMatch(re) => {
+            let idx = lines.iter().position(|l| re.is_match(strip_eol(l))).ok_or_else(|| {
+                EzError::NotFound(format!("No line matching '{}' in '{}'", re.as_str(), file.display()))
+            })?;
+            push_lines(&mut out, &lines[..=idx], newline);
+            push_lines(&mut out, &[block], newline);
+            push_lines(&mut out, &lines[idx + 1..], newline);
+        }
+        Operation::DeleteLines(start, end) => {
+            if *end > lines.len() {
+                return Err(EzError::InvalidArgs(format!(
+                    "Lines {}-{} are past the end of '{}' ({} lines)", start, end, file.display(), lines.len()
+                )));
+            }
+            push_lines(&mut out, &lines[..start - 1], newline);
+            push_lines(&mut out, &lines[*end..], newline);
+            // Deleting the last line of a file without a trailing newline leaves one behind
+            if *end == lines.len() && !contents.ends_with('\n') && out.ends_with(newline) {
+                out.truncate(out.len() - newline.len());
+            }
+        }
+        Operation::Between(start_marker, end_marker) => {
+            let start = lines.iter().position(|l| l.contains(start_marker.as_str())).ok_or_else(|| {
+                EzError::NotFound(format!("Start marker '{}' not found in '{}'", start_marker, file.display()))
+            })?;
+            let end = lines[start + 1..].iter().position(|l| l.contains(end_marker.as_str()))
+                .map(|i| start + 1 + i)
+                .ok_or_else(|| {
+                    EzError::NotFound(format!("End marker '{}' not found after line {} in '{}'", end_marker, start + 1, file.display()))
+                })?;
+            // With a second block it's unclear which one is meant
+            if lines.iter().enumerate().any(|(i, l)| i != start && i != end && l.contains(start_marker.as_str())) {
+                return Err(EzError::InvalidArgs(format!(
+                    "Start marker '{}' appears more than once in '{}'. Use markers that are unique", start_marker, file.display()
+                )));
+            }
+            push_lines(&mut out, &lines[..=start], newline);
+            push_lines(&mut out, &[block], newline);
+            push_lines(&mut out, &lines[end..], newline);
+        }
+        Operation::AppendIfMissing(line) => {
+            out.push_str(contents);
+            if !lines.iter().any(|l| strip_eol(l) == line) {
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push_str(newline);
+                }
+                out.push_str(line);
+                out.push_str(newline);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Append lines, making sure a final line without a newline gets one before
+/// more text is placed after it.
+fn push_lines(out: &mut String, lines: &[&str], newline: &str) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push_str(newline);
+    }
+    for line in lines {
+        out.push_str(line);
+    }
+}
+
+fn strip_eol(line: &str) -> &str {
+    line.trim_end_matches('\n').trim_end_matches('\r')
+}
+
+/// Normalize inserted text to the file's line endings, ending with a newline.
+fn to_block(text: &str, newline: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    let mut block = text.replace("\r\n", "\n").replace('\n', newline);
+    if !block.ends_with(newline) {
+        block.push_str(newline);
+    }
+    block
+}
+
+/// Parse "10-20", "10:20" or "15" into a 1-based inclusive range.
+fn parse_range(range: &str) -> Result<(usize, usize), EzError> {
+    let invalid = || EzError::InvalidArgs(format!("Invalid line range '{}'. Use START-END, e.g. 10-20", range));
+    let (start, end) = match range.split_once(['-', ':']) {
+        Some((a, b)) => (a.trim().parse().map_err(|_| invalid())?, b.trim().parse().map_err(|_| invalid())?),
+        None => {
+            let n = range.trim().parse().map_err(|_| invalid())?;
+            (n, n)
+        }
+    };
+    if start == 0 || end < start {
+        return Err(invalid());
+    }
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(op: Operation, contents: &str, text: &str) -> Result<String, EzError> {
+        let newline = if contents.contains("\r\n") { "\r\n" } else { "\n" };
+        let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+        apply(&op, &lines, contents, &to_block(text, newline), newline, std::path::Path::new("f.txt"))
+    }
+
+    fn ok(op: Operation, contents: &str, text: &str) -> String {
+        let Ok(out) = edit(op, contents, text) else { panic!("edit failed") };
+        out
+    }
+
+    #[test]
+    fn insert_after_first_and_last_line() {
+        assert_eq!(ok(Operation::InsertAfter(0), "a\nb\n", "x"), "x\na\nb\n");
+        assert_eq!(ok(Operation::InsertAfter(1), "a\nb\n", "x"), "a\nx\nb\n");
+        assert_eq!(ok(Operation::InsertAfter(2), "a\nb\n", "x"), "a\nb\nx\n");
+        // A last line without a newline gets one before the insert
+        assert_eq!(ok(Operation::InsertAfter(2), "a\nb", "x"), "a\nb\nx\n");
+        assert!(matches!(edit(Operation::InsertAfter(3), "a\nb\n", "x"), Err(EzError::InvalidArgs(_))));
+    }
+
+    #[test]
+    fn delete_first_last_and_out_of_range_lines() {
+        assert_eq!(ok(Operation::DeleteLines(1, 1), "a\nb\nc\n", ""), "b\nc\n");
+        assert_eq!(ok(Operation::DeleteLines(3, 3), "a\nb\nc\n", ""), "a\nb\n");
+        assert_eq!(ok(Operation::DeleteLines(2, 3), "a\nb\nc", ""), "a");
+        assert_eq!(ok(Operation::DeleteLines(1, 3), "a\nb\nc\n", ""), "");
+        assert!(matches!(edit(Operation::DeleteLines(3, 4), "a\nb\nc\n", ""), Err(EzError::InvalidArgs(_))));
+    }
+
+    #[test]
+    fn line_ranges_are_parsed() {
+        assert_eq!(parse_range("10-20").ok(), Some((10, 20)));
+        assert_eq!(parse_range("3:4").ok(), Some((3, 4)));
+        assert_eq!(parse_range("15").ok(), Some((15, 15)));
+        for bad in ["0", "0-2", "5-3", "a-b", ""] {
+            assert!(parse_range(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn between_replaces_only_the_inside() {
+        let file = "x\n# BEGIN\nold\nold\n# END\ny\n";
+        assert_eq!(ok(Operation::Between("# BEGIN".into(), "# END".into()), file, "new"), "x\n# BEGIN\nnew\n# END\ny\n");
+        // Adjacent markers have nothing between them yet
+        assert_eq!(ok(Operation::Between("<<".into(), ">>".into()), "<<\n>>\n", "new\n"), "<<\nnew\n>>\n");
+    }
+
+    #[test]
+    fn missing_or_duplicated_markers_are_refused() {
+        let between = || Operation::Between("# BEGIN".into(), "# END".into());
+        assert!(matches!(edit(between(), "a\n# END\n", "x"), Err(EzError::NotFound(_))));
+        assert!(matches!(edit(between(), "# END\n# BEGIN\na\n", "x"), Err(EzError::NotFound(_))));
+        let twice = "# BEGIN\na\n# END\n# BEGIN\nb\n# END\n";
+        assert!(matches!(edit(between(), twice, "x"), Err(EzError::InvalidArgs(_))));
+    }
+
+    #[test]
+    fn crlf_files_stay_crlf() {
+        assert_eq!(ok(Operation::InsertAfter(1), "a\r\nb\r\n", "x\ny"), "a\r\nx\r\ny\r\nb\r\n");
+        assert_eq!(ok(Operation::DeleteLines(2, 2), "a\r\nb\r\nc", ""), "a\r\nc");
+        assert_eq!(ok(Operation::DeleteLines(3, 3), "a\r\nb\r\nc", ""), "a\r\nb");
+        assert_eq!(ok(Operation::Between("[".into(), "]".into()), "[\r\nold\r\n]\r\n", "new"), "[\r\nnew\r\n]\r\n");
+        assert_eq!(ok(Operation::AppendIfMissing("z".into()), "a\r\nb", ""), "a\r\nb\r\nz\r\n");
+        assert_eq!(ok(Operation::AppendIfMissing("b".into()), "a\r\nb\r\n", ""), "a\r\nb\r\n");
+    }
+
+    #[test]
+    fn insert_after_the_first_matching_line() {
+        let re = Regex::new("^b").unwrap();
+        assert_eq!(ok(Operation::InsertAfterMatch(re), "a\nb\nb\n", "x"), "a\nb\nx\nb\n");
+        let re = Regex::new("zzz").unwrap();
+        assert!(matches!(edit(Operation::InsertAfterMatch(re), "a\n", "x"), Err(EzError::NotFound(_))));
+    }
+}
@@ -1,127 +1,648 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use colored::*;
-use walkdir::WalkDir;
 use regex::Regex;
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
+use crate::scan::LineReader;
+use crate::walk::WalkOptions;
+use crate::EntryType;
 
+/// Everything that narrows down which entries `find` reports.
+pub struct Filters {
+    pub glob: bool,
+    pub regex: bool,
+    pub entry_type: Option<EntryType>,
+    pub extensions: Vec<String>,
+    pub min_size: Option<String>,
+    pub max_size: Option<String>,
+    pub newer: Option<String>,
+    pub older: Option<String>,
+    pub created: bool,
+    pub depth: Option<usize>,
+    pub hidden: bool,
+    pub no_ignore: bool,
+}
+
+enum NamePattern {
+    Regex(Regex),
+    Glob(globset::GlobMatcher),
+}
+
+/// `Filters` with sizes, ages and patterns parsed.
+struct Matcher {
+    name: Option<NamePattern>,
+    entry_type: Option<EntryType>,
+    extensions: Vec<String>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    newer: Option<SystemTime>,
+    older: Option<SystemTime>,
+    created: bool,
+}
+
+impl Matcher {
+    fn new(pattern: Option<&str>, ignore_case: bool, filters: &Filters) -> Result<Self, EzError> {
+        let name = match pattern {
+            None => None,
+            Some(p) if filters.glob => {
+                let glob = globset::GlobBuilder::new(p)
+                    .case_insensitive(ignore_case)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| EzError::InvalidArgs(format!("Invalid glob '{}': {}", p, e)))?;
+                Some(NamePattern::Glob(glob.compile_matcher()))
+            }
+            Some(p) if filters.regex => {
+                let p = if ignore_case { format!("(?i){}", p) } else { p.to_string() };
+                Some(NamePattern::Regex(Regex::new(&p).map_err(|e| EzError::InvalidArgs(format!("Invalid pattern: {}", e)))?))
+            }
+            // Plain text matches anywhere in the name, ignoring case
+            Some(p) => Some(NamePattern::Regex(Regex::new(&format!("(?i){}", regex::escape(p)))
+                .map_err(|e| EzError::InvalidArgs(format!("Invalid pattern: {}", e)))?)),
+        };
+
+        let size = |value: &Option<String>| -> Result<Option<u64>, EzError> {
+            value.as_deref().map(|s| crate::utils::parse_size(s)
+                .ok_or_else(|| EzError::InvalidArgs(format!("Invalid size '{}'. Use e.g. 100KB, 1.5MB or 2G", s))))
+                .transpose()
+        };
+
+        Ok(Matcher {
+            name,
+            entry_type: filters.entry_type,
+            extensions: filters.extensions.iter()
+                .map(|e| e.trim_start_matches('.').to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect(),
+            min_size: size(&filters.min_size)?,
+            max_size: size(&filters.max_size)?,
+            newer: filters.newer.as_deref().map(parse_age).transpose()?,
+            older: filters.older.as_deref().map(parse_age).transpose()?,
+            created: filters.created,
+        })
+    }
+
+    fn matches(&self, entry: &ignore::DirEntry) -> bool {
+        let Some(file_type) = entry.file_type() else { return false };
+        let kind = if file_type.is_symlink() {
+            EntryType::Symlink
+        } else if file_type.is_dir() {
+            EntryType::Dir
+        } else {
+            EntryType::File
+        };
+        if self.entry_type.is_some_and(|t| t != kind) {
+            return false;
+        }
+
+        let matched = match &self.name {
+            None => true,
+            Some(NamePattern::Regex(re)) => re.is_match(&entry.file_name().to_string_lossy()),
+            Some(NamePattern::Glob(glob)) => glob.is_match(entry.file_name()),
+        };
+        if !matched {
+            return false;
+        }
+
+        if !self.extensions.is_empty() {
+            let ext = entry.path().extension().map(|e| e.to_string_lossy().to_lowercase());
+            if !ext.is_some_and(|e| self.extensions.contains(&e)) {
+                return false;
+            }
+        }
+
+        if self.min_size.is_none() && self.max_size.is_none() && self.newer.is_none() && self.older.is_none() {
+            return true;
+        }
+        let Ok(metadata) = entry.metadata() else { return false };
+
+        // Folder sizes aren't meaningful here, so a size filter only matches files
+        if self.min_size.is_some() || self.max_size.is_some() {
+            if kind != EntryType::File {
+                return false;
+            }
+            if self.min_size.is_some_and(|min| metadata.len() < min) || self.max_size.is_some_and(|max| metadata.len() > max) {
+                return false;
+            }
+        }
+
+        if self.newer.is_some() || self.older.is_some() {
+            let time = if self.created { metadata.created() } else { metadata.modified() };
+            let Ok(time) = time else { return false };
+            if self.newer.is_some_and(|cutoff| time < cutoff) || self.older.is_some_and(|cutoff| time > cutoff) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// `2d`, `3h`, `1week` mean that long ago; anything else is read as a date.
+fn parse_age(value: &str) -> Result<SystemTime, EzError> {
+    if let Ok(duration) = humantime::parse_duration(value.trim()) {
+        return Ok(SystemTime::now().checked_sub(duration).unwrap_or(SystemTime::UNIX_EPOCH));
+    }
+    crate::utils::parse_time(value)
+        .map(SystemTime::from)
+        .map_err(|_| EzError::InvalidArgs(format!(
+            "Invalid age '{}'. Use a duration like 2d, 3h, 1week or a date like 2024-01-31", value
+        )))
+}
+
+/// Honor .gitignore/.ignore (unless `no_ignore`), hidden-file and depth settings.
+fn walk_options(filters: &Filters, threads: usize) -> WalkOptions {
+    WalkOptions {
+        hidden: filters.hidden,
+        respect_ignore: !filters.no_ignore,
+        skip_git: true,
+        max_depth: filters.depth,
+        threads,
+    }
+}
+
+/// Echo the active filters back in JSON metadata.
+fn filters_json(pattern: Option<&str>, filters: &Filters) -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "match": if filters.glob { "glob" } else if filters.regex { "regex" } else { "substring" },
+        "respect_ignore": !filters.no_ignore,
+        "hidden": filters.hidden,
+    });
+    if let Some(p) = pattern {
+        json["pattern"] = serde_json::json!(p);
+    }
+    if let Some(t) = filters.entry_type {
+        json["type"] = serde_json::json!(match t {
+            EntryType::File => "file",
+            EntryType::Dir => "dir",
+            EntryType::Symlink => "symlink",
+        });
+    }
+    if !filters.extensions.is_empty() {
+        json["extensions"] = serde_json::json!(filters.extensions);
+    }
+    for (key, value) in [("min_size", &filters.min_size), ("max_size", &filters.max_size),
+                         ("newer", &filters.newer), ("older", &filters.older)] {
+        if let Some(v) = value {
+            json[key] = serde_json::json!(v);
+        }
+    }
+    if filters.newer.is_some() || filters.older.is_some() {
+        json["time"] = serde_json::json!(if filters.created { "created" } else { "modified" });
+    }
+    if let Some(d) = filters.depth {
+        json["depth"] = serde_json::json!(d);
+    }
+    json
+}
+
+/// Something to do with every match instead of just listing it.
+pub enum Action {
+    /// Run a shell command once per match; `{}` is replaced by the path.
+    Exec(String),
+    /// Run a shell command with all matches in place of `{}`, split into as
+    /// few runs as the system's argument length limit allows.
+    ExecBatch(String),
+    Delete,
+    CopyTo(PathBuf),
+    PackInto(PathBuf),
+    Chmod(String),
+}
+
+impl Action {
+    fn name(&self) -> &'static str {
+        match self {
+            Action::Exec(_) => "exec",
+            Action::ExecBatch(_) => "exec-batch",
+            Action::Delete => "delete",
+            Action::CopyTo(_) => "copy-to",
+            Action::PackInto(_) => "pack-into",
+            Action::Chmod(_) => "chmod",
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
     pattern: String,
     path: PathBuf,
     inside: bool,
     ignore_case: bool,
     line_numbers: bool,
+    filters: Filters,
+    action: Option<Action>,
+    threads: usize,
     ctx: &CommandContext,
 ) -> Result<CommandOutput, EzError> {
-    if inside {
-        find_in_contents(pattern, path, ignore_case, line_numbers, ctx)
+    // With an action, the action reports on each item instead of the plain listing
+    let list = action.is_none();
+    let walk = walk_options(&filters, threads);
+    let (mut output, matched) = if inside {
+        find_in_contents(pattern, &path, ignore_case, line_numbers, &filters, &walk, list, ctx)?
+    } else {
+        // `*` on its own matches every name, leaving the choice to the other filters
+        let pattern = Some(pattern).filter(|p| p != "*");
+        find_files(pattern, &path, ignore_case, &filters, &walk, list, ctx)?
+    };
+
+    let Some(action) = action else { return Ok(output) };
+    let result = if matched.is_empty() {
+        if !ctx.json {
+            println!("{} Nothing matched, so nothing to {}", "ℹ".blue(), action.name());
+        }
+        serde_json::json!(null)
+    } else {
+        run_action(&action, &path, matched, ctx)?
+    };
+
+    let metadata = output.metadata.get_or_insert_with(|| serde_json::json!({}));
+    metadata["action"] = serde_json::json!({ "name": action.name(), "result": result });
+    if ctx.dry_run {
+        metadata["dry_run"] = serde_json::json!(true);
+    }
+    Ok(output)
+}
+
+fn run_action(action: &Action, root: &Path, matched: Vec<PathBuf>, ctx: &CommandContext) -> Result<serde_json::Value, EzError> {
+    match action {
+        Action::Exec(command) => {
+            let mut runs = Vec::new();
+            for path in &matched {
+                runs.push(run_command(&fill_placeholder(command, std::slice::from_ref(path)), ctx)?);
+            }
+            exec_summary(runs, ctx)
+        }
+        Action::ExecBatch(command) => {
+            let mut runs = Vec::new();
+            for batch in batches(command, &matched, max_command_len()) {
+                runs.push(run_command(&fill_placeholder(command, batch), ctx)?);
+            }
+            exec_summary(runs, ctx)
+        }
+        Action::Delete => {
+            let paths = outermost(matched);
+            if !ctx.dry_run {
+                confirm(&format!("Delete {} matched item(s)?", paths.len()), "delete without asking", ctx)?;
+            }
+            // Already confirmed as a batch, so skip remove's per-folder prompt
+            Ok(crate::commands::remove::execute(paths, true, true, ctx)?.data)
+        }
+        Action::Chmod(mode) => {
+            Ok(crate::commands::permissions::set(matched, vec![mode.clone()], false, None, None, ctx)?.data)
+        }
+        Action::CopyTo(dest) => {
+            let pairs: Vec<(PathBuf, PathBuf)> = outermost(matched).into_iter()
+                .map(|from| {
+                    let to = dest.join(from.strip_prefix(root).unwrap_or(&from));
+                    (from, to)
+                })
+                .collect();
+            let existing = pairs.iter().filter(|(_, to)| to.exists()).count();
+            if existing > 0 && !ctx.dry_run {
+                confirm(&format!("{} item(s) already exist in '{}'. Overwrite?", existing, dest.display()), "overwrite", ctx)?;
+            }
+
+            let mut copied = Vec::new();
+            for (from, to) in pairs {
+                if ctx.dry_run {
+                    if !ctx.json {
+                        println!("{} Would copy '{}' to '{}'", "~".dimmed(), from.display(), to.display());
+                    }
+                } else {
+                    if let Some(parent) = to.parent() {
+                        fs::create_dir_all(parent)
+                            .map_err(|e| EzError::General(format!("Cannot create folder '{}': {}", parent.display(), e)))?;
+                    }
+                    crate::commands::copy::execute(from.clone(), to.clone(), true, false, false, ctx)?;
+                }
+                copied.push(serde_json::json!({ "from": from.display().to_string(), "to": to.display().to_string() }));
+            }
+            Ok(serde_json::json!({ "copied": copied }))
+        }
+        Action::PackInto(archive) => {
+            // The archive may land inside the folder being searched; never pack it into itself
+            let files: Vec<PathBuf> = outermost(matched).into_iter()
+                .filter(|p| !same_file(p, archive))
+                .collect();
+            if archive.exists() && !ctx.dry_run {
+                confirm(&format!("'{}' already exists. Overwrite?", archive.display()), "overwrite", ctx)?;
+            }
+            if ctx.dry_run {
+                if !ctx.json {
+                    println!("{} Would pack {} item(s) into '{}'", "~".dimmed(), files.len(), archive.display());
+                }
+                return Ok(serde_json::json!({
+                    "archive": archive.display().to_string(),
+                    "files": files.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+                }));
+            }
+            Ok(crate::commands::pack::execute(archive.clone(), files, None, ctx)?.data)
+        }
+    }
+}
+
+/// Ask before a bulk change; without a terminal, only `--yes` lets it through.
+fn confirm(question: &str, what: &str, ctx: &CommandContext) -> Result<(), EzError> {
+    if ctx.should_confirm() {
+        print!("{} [y/N] ", question);
+        std::io::stdout().flush().unwrap();
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+        if !input.trim().eq_ignore_ascii_case("y") {
+            return Err(EzError::Cancelled("Cancelled by user".to_string()));
+        }
+    } else if !ctx.yes {
+        return Err(EzError::Cancelled(format!("Cannot confirm in non-interactive mode. Use --yes to {}", what)));
+    }
+    Ok(())
+}
+
+/// Drop matches that sit inside another matched folder; acting on the folder covers them.
+fn outermost(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths.sort();
+    let mut kept: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if !kept.iter().any(|k| path.starts_with(k)) {
+            kept.push(path);
+        }
+    }
+    kept
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Put the shell-quoted paths where `{}` is, or at the end if there's no `{}`.
+fn fill_placeholder(command: &str, paths: &[PathBuf]) -> String {
+    let quoted = paths.iter().map(|p| shell_quote(&p.display().to_string())).collect::<Vec<_>>().join(" ");
+    if command.contains("{}") {
+        command.replace("{}", &quoted)
     } else {
-        find_files(pattern, path, ctx)
+        format!("{} {}", command, quoted)
     }
 }
 
-fn find_files(pattern: String, path: PathBuf, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
-    let re = Regex::new(&format!("(?i){}", regex::escape(&pattern)))
-        .map_err(|e| EzError::InvalidArgs(format!("Invalid pattern: {}", e)))?;
+/// Split `paths` into runs of `command` that each stay within `max_len` bytes
+/// once filled in, like find's `-exec {} +` and xargs do. A path too long to
+/// share a run gets one to itself.
+fn batches<'a>(command: &str, paths: &'a [PathBuf], max_len: usize) -> Vec<&'a [PathBuf]> {
+    let slots = command.matches("{}").count();
+    let (base, slots) = if slots == 0 { (command.len() + 1, 1) } else { (command.len() - 2 * slots, slots) };
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut len = base;
+    for (i, path) in paths.iter().enumerate() {
+        // Each path adds itself (quoted) and a separating space in every slot
+        let extra = slots * (shell_quote(&path.display().to_string()).len() + usize::from(i > start));
+        if i > start && len + extra > max_len {
+            batches.push(&paths[start..i]);
+            start = i;
+            len = base + extra - slots;
+        } else {
+            len += extra;
+        }
+    }
+    if start < paths.len() {
+        batches.push(&paths[start..]);
+    }
+    batches
+}
+
+/// Longest command `sh -c` can be handed. The whole command is one argument,
+/// which Linux caps at 128 KiB on top of ARG_MAX (shared with the environment);
+/// like xargs, 2 KiB are left spare.
+fn max_command_len() -> usize {
+    // SAFETY: sysconf only reads a system setting
+    let arg_max = unsafe { libc::sysconf(libc::_SC_ARG_MAX) };
+    let arg_max = if arg_max > 0 { arg_max as usize } else { 128 * 1024 };
+    let env: usize = std::env::vars_os()
+        .map(|(k, v)| k.len() + v.len() + 2 + std::mem::size_of::<usize>())
+        .sum();
+    let limit = arg_max.saturating_sub(env).saturating_sub(2048);
+    if cfg!(target_os = "linux") {
+        limit.min(128 * 1024 - 1)
+    } else {
+        limit
+    }
+}
+
+fn shell_quote(text: &str) -> String {
+    if !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || "/._-+,:@".contains(c)) {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', "'\\''"))
+    }
+}
+
+/// Run through `sh -c`. Output goes straight to the terminal, or is captured for JSON.
+fn run_command(command: &str, ctx: &CommandContext) -> Result<serde_json::Value, EzError> {
+    if ctx.dry_run {
+        if !ctx.json {
+            println!("{} Would run: {}", "~".dimmed(), command);
+        }
+        return Ok(serde_json::json!({ "command": command }));
+    }
+
+    let mut cmd = std::process::Command::new("sh");
+    cmd.arg("-c").arg(command);
+    let mut run = serde_json::json!({ "command": command });
+    if ctx.json {
+        let output = cmd.output()
+            .map_err(|e| EzError::General(format!("Cannot run '{}': {}", command, e)))?;
+        run["exit_code"] = serde_json::json!(output.status.code());
+        run["stdout"] = serde_json::json!(String::from_utf8_lossy(&output.stdout));
+        run["stderr"] = serde_json::json!(String::from_utf8_lossy(&output.stderr));
+    } else {
+        let status = cmd.status()
+            .map_err(|e| EzError::General(format!("Cannot run '{}': {}", command, e)))?;
+        if !status.success() {
+            println!("{} '{}' failed ({})", "✗".red(), command, status);
+        }
+        run["exit_code"] = serde_json::json!(status.code());
+    }
+    Ok(run)
+}
+
+fn exec_summary(runs: Vec<serde_json::Value>, ctx: &CommandContext) -> Result<serde_json::Value, EzError> {
+    let failed = runs.iter().filter(|r| r.get("exit_code").is_some_and(|c| c != 0)).count();
+    if !ctx.json && !ctx.dry_run && ctx.is_tty {
+        if failed == 0 {
+            println!("{} Ran {} command(s)", "✓".green(), runs.len());
+        } else {
+            println!("{} {} of {} command(s) failed", "⚠️".yellow(), failed, runs.len());
+        }
+    }
+    Ok(serde_json::json!({ "runs": runs, "failed": failed }))
+}
+
+fn find_files(
+    pattern: Option<String>,
+    path: &Path,
+    ignore_case: bool,
+    filters: &Filters,
+    walk: &WalkOptions,
+    list: bool,
+    ctx: &CommandContext,
+) -> Result<(CommandOutput, Vec<PathBuf>), EzError> {
+    let matcher = Matcher::new(pattern.as_deref(), ignore_case, filters)?;
 
     let mut found = 0;
     let mut results = Vec::new();
+    let mut matched = Vec::new();
 
-    for entry in WalkDir::new(&path).into_iter().filter_map(|e| e.ok()) {
-        let name = entry.file_name().to_string_lossy();
-
-        if re.is_match(&name) {
-            let path_display = entry.path().strip_prefix(&path).unwrap_or(entry.path());
-            let path_str = path_display.display().to_string();
-            results.push(serde_json::json!({
-                "path": path_str,
-                "type": if entry.file_type().is_dir() { "directory" } else { "file" },
-            }));
+    let entries = crate::walk::walk(path, walk, |entry| {
+        if entry.depth() == 0 || !matcher.matches(entry) {
+            return None;
+        }
 
-            if !ctx.json {
-                println!("{}", path_str.green());
+        let path_display = entry.path().strip_prefix(path).unwrap_or(entry.path());
+        let kind = match entry.file_type() {
+            Some(t) if t.is_symlink() => "symlink",
+            Some(t) if t.is_dir() => "directory",
+            _ => "file",
+        };
+        let mut result = serde_json::json!({
+            "path": path_display.display().to_string(),
+            "type": kind,
+        });
+        if let Ok(metadata) = entry.metadata() {
+            if kind == "file" {
+                result["size"] = serde_json::json!(metadata.len());
+            }
+            if let Ok(modified) = metadata.modified() {
+                result["modified"] = serde_json::json!(crate::utils::format_time_iso8601(modified));
             }
-            found += 1;
         }
+        Some(result)
+    });
+
+    for (entry_path, result) in entries {
+        if !ctx.json && list && ctx.is_tty {
+            let path_str = result["path"].as_str().unwrap_or_default();
+            println!("{}", path_str.green());
+        } else if !ctx.json && list {
+            // Piped paths must resolve from the current folder, not the search root
+            println!("{}", entry_path.display());
+        }
+        results.push(result);
+        matched.push(entry_path);
+        found += 1;
     }
 
-    if !ctx.json {
+    // Keep piped output to bare paths so it can feed `ez rename`, `ez remove`, ...
+    if !ctx.json && list && ctx.is_tty {
         if found == 0 {
-            println!("{} No files matching '{}' found", "ℹ".blue(), pattern);
+            match &pattern {
+                Some(p) => println!("{} No files matching '{}' found", "ℹ".blue(), p),
+                None => println!("{} No files match these filters", "ℹ".blue()),
+            }
         } else {
             println!("\n{} Found {} file(s)", "✓".green(), found);
         }
     }
 
-    Ok(CommandOutput::new("find", serde_json::json!(results)))
+    let output = CommandOutput::new("find", serde_json::json!(results))
+        .with_metadata(serde_json::json!({
+            "total": found,
+            "mode": "name",
+            "filters": filters_json(pattern.as_deref(), filters),
+        }));
+    Ok((output, matched))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn find_in_contents(
     pattern: String,
-    path: PathBuf,
+    path: &Path,
     ignore_case: bool,
     line_numbers: bool,
+    filters: &Filters,
+    walk: &WalkOptions,
+    list: bool,
     ctx: &CommandContext,
-) -> Result<CommandOutput, EzError> {
+) -> Result<(CommandOutput, Vec<PathBuf>), EzError> {
     let re = if ignore_case {
         Regex::new(&format!("(?i){}", regex::escape(&pattern)))
     } else {
         Regex::new(&regex::escape(&pattern))
     }.map_err(|e| EzError::InvalidArgs(format!("Invalid pattern: {}", e)))?;
+    // The pattern is for contents here; the other filters still pick the files
+    let matcher = Matcher::new(None, ignore_case, filters)?;
 
     let mut found_files = 0;
     let mut found_matches = 0;
     let mut results = Vec::new();
+    let mut matched = Vec::new();
 
-    for entry in WalkDir::new(&path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-    {
-        if let Ok(contents) = fs::read_to_string(entry.path()) {
-            let mut file_matches = Vec::new();
-
-            for (line_num, line) in contents.lines().enumerate() {
-                if re.is_match(line) {
-                    file_matches.push(serde_json::json!({
-                        "line": line_num + 1,
-                        "text": line,
-                    }));
-                    found_matches += 1;
+    // Files are read and matched on the walker's threads; printing happens in path order
+    let entries = crate::walk::walk(path, walk, |entry| {
+        if !entry.file_type().is_some_and(|t| t.is_file()) || !matcher.matches(entry) {
+            return None;
+        }
+        // Streamed line by line; binary files only report that they match
+        let mut reader = LineReader::open(entry.path()).ok()?;
+        let mut file_matches = Vec::new();
+        while let Ok(Some(line)) = reader.next_line() {
+            if re.is_match(&line.text) {
+                let number = line.number;
+                let text = line.text.into_owned();
+                if reader.binary {
+                    return Some((Vec::new(), true));
                 }
+                file_matches.push(serde_json::json!({
+                    "line": number,
+                    "text": text,
+                }));
             }
+        }
+        if reader.binary && !file_matches.is_empty() {
+            return Some((Vec::new(), true));
+        }
+        (!file_matches.is_empty()).then_some((file_matches, false))
+    });
 
-            if !file_matches.is_empty() {
-                found_files += 1;
-                let path_display = entry.path().strip_prefix(&path).unwrap_or(entry.path());
-                let path_str = path_display.display().to_string();
-
-                results.push(serde_json::json!({
-                    "file": path_str,
-                    "matches": file_matches,
-                }));
+    for (entry_path, (file_matches, binary)) in entries {
+        found_files += 1;
+        found_matches += file_matches.len();
+        let path_display = entry_path.strip_prefix(path).unwrap_or(&entry_path);
+        let path_str = path_display.display().to_string();
 
-                if !ctx.json {
-                    println!("\n{}", path_str.cyan().underline());
-                    for m in &file_matches {
-                        let ln = m["line"].as_u64().unwrap_or(0);
-                        let text = m["text"].as_str().unwrap_or("");
-                        let highlighted = re.replace_all(text, |caps: &regex::Captures| {
-                            caps[0].to_string().red().bold().to_string()
-                        });
-                        if line_numbers {
-                            println!("  {:>4} │ {}", ln.to_string().dimmed(), highlighted);
-                        } else {
-                            println!("  {}", highlighted);
-                        }
-                    }
+        if !ctx.json && list && binary {
+            println!("\n{} {}", path_str.cyan().underline(), "(binary file matches)".dimmed());
+        } else if !ctx.json && list {
+            println!("\n{}", path_str.cyan().underline());
+            for m in &file_matches {
+                let ln = m["line"].as_u64().unwrap_or(0);
+                let text = m["text"].as_str().unwrap_or("");
+                let highlighted = re.replace_all(text, |caps: &regex::Captures| {
+                    caps[0].to_string().red().bold().to_string()
+                });
+                if line_numbers {
+                    println!("  {:>4} │ {}", ln.to_string().dimmed(), highlighted);
+                } else {
+                    println!("  {}", highlighted);
                 }
             }
         }
+
+        let mut result = serde_json::json!({
+            "file": path_str,
+            "matches": file_matches,
+        });
+        if binary {
+            result["binary"] = serde_json::json!(true);
+        }
+        results.push(result);
+        matched.push(entry_path);
     }
 
-    if !ctx.json {
+    if !ctx.json && list {
         if found_matches == 0 {
             println!("{} No matches for '{}' found", "ℹ".blue(), pattern);
         } else {
@@ -130,10 +651,57 @@ fn find_in_contents(
         }
     }
 
-    Ok(CommandOutput::new("find", serde_json::json!(results))
+    let output = CommandOutput::new("find", serde_json::json!(results))
         .with_metadata(serde_json::json!({
             "total_matches": found_matches,
             "total_files": found_files,
             "mode": "content",
-        })))
+            "filters": filters_json(None, filters),
+        }));
+    Ok((output, matched))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn fill_placeholder_quotes_paths() {
+        let files = paths(&["a.txt", "my file.txt", "it's"]);
+        assert_eq!(fill_placeholder("wc -l {}", &files), r#"wc -l a.txt 'my file.txt' 'it'\''s'"#);
+        assert_eq!(fill_placeholder("cat", &files[..1]), "cat a.txt");
+        assert_eq!(fill_placeholder("cp {} {}.bak", &files[..1]), "cp a.txt a.txt.bak");
+    }
+
+    #[test]
+    fn batches_stay_under_the_limit() {
+        let files: Vec<PathBuf> = (0..100).map(|i| PathBuf::from(format!("dir/file-{:03}.txt", i))).collect();
+        let batches = batches("cat {}", &files, 200);
+        assert!(batches.len() > 1);
+        assert_eq!(batches.iter().map(|b| b.len()).sum::<usize>(), files.len());
+        for batch in &batches {
+            assert!(fill_placeholder("cat {}", batch).len() <= 200);
+        }
+        // Every batch is as full as it can be
+        for pair in batches.windows(2) {
+            let mut grown = pair[0].to_vec();
+            grown.push(pair[1][0].clone());
+            assert!(fill_placeholder("cat {}", &grown).len() > 200);
+        }
+    }
+
+    #[test]
+    fn batches_count_every_placeholder() {
+        let files = paths(&["aaaa", "bbbb", "cccc"]);
+        // "echo aaaa bbbb; echo aaaa bbbb" is 30 bytes
+        let split: Vec<usize> = batches("echo {}; echo {}", &files, 30).iter().map(|b| b.len()).collect();
+        assert_eq!(split, [2, 1]);
+        assert_eq!(batches("echo", &files, 1000).len(), 1);
+        // Too long to share a run, but still run
+        assert_eq!(batches("echo", &files, 3).len(), 3);
+    }
 }
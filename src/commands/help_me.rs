@@ -24,9 +24,11 @@ fn show_all_help(ctx: &CommandContext) {
         ("find / search", "Find files or text", "ez find pattern --inside --ignore-case"),
         ("copy / cp", "Copy files/folders", "ez copy from to --recursive --progress"),
         ("move / mv", "Move or rename files", "ez move old new"),
+        ("rename", "Rename many files by pattern", "ez rename 'IMG_(\\d+)' 'photo-{n:3}' '*.jpg' --dry-run"),
         ("remove / rm", "Remove files/folders", "ez remove path --recursive --force"),
         ("create-folder", "Create directories", "ez create-folder path --parents"),
         ("create-file", "Create empty files", "ez create-file path"),
+        ("scaffold", "Create a project tree from a spec", "ez scaffold spec.yaml"),
         ("where / here", "Show current location", "ez where"),
         ("size / usage", "Show folder sizes", "ez size [path] --detailed"),
         ("running / ps", "Show running processes", "ez running --all --filter name"),
@@ -34,11 +36,17 @@ fn show_all_help(ctx: &CommandContext) {
         ("download", "Download files", "ez download url --save filename --progress"),
         ("pack", "Create archives", "ez pack archive.zip files..."),
         ("unpack", "Extract archives", "ez unpack archive.tar.gz --to folder"),
+        ("split", "Split a big file into chunks", "ez split file.iso --size 100MB"),
+        ("join", "Rebuild a split file", "ez join file.iso.manifest.json"),
         ("space / disk", "Show disk space", "ez space"),
+        ("normalize", "Fix encodings and line endings", "ez normalize . --check"),
         ("count", "Count lines/words/bytes", "ez count files..."),
         ("sort", "Sort file contents", "ez sort file --reverse --numeric --unique"),
         ("compare / diff", "Compare files", "ez compare file1 file2 --side-by-side"),
+        ("edit", "Line and marker based edits", "ez edit notes.md --insert-after 3 --text \"new line\""),
+        ("config-edit", "Edit JSON/TOML/YAML keys", "ez config-edit Cargo.toml set package.version=\"1.2.0\""),
         ("make-runnable", "Make file executable", "ez make-runnable script.sh"),
+        ("link", "Create or check symlinks", "ez link target name"),
     ];
 
     for (name, desc, example) in commands {
@@ -56,7 +64,12 @@ fn show_command_help(cmd: &str, ctx: &CommandContext) -> Result<CommandOutput, E
     let help = match cmd {
         "list" | "ls" => "List files and folders (ls replacement)\n\nUSAGE:\n    ez list [PATH] [OPTIONS]\n\nOPTIONS:\n    -a, --all       Show hidden files\n    -d, --details   Show size and modification date\n    -t, --time      Sort by modification time\n    -s, --size      Sort by file size",
         "show" | "cat" => "Show file contents (cat replacement)\n\nUSAGE:\n    ez show FILE [OPTIONS]\n\nOPTIONS:\n    -n, --numbers   Show line numbers\n    -f, --first N   Show only first N lines\n    -l, --last N    Show only last N lines",
-        "find" | "search" => "Find files or search inside files\n\nUSAGE:\n    ez find PATTERN [PATH] [OPTIONS]\n\nOPTIONS:\n    -i, --inside        Search inside file contents\n    -c, --ignore-case   Case insensitive\n    -n, --line-numbers  Show line numbers",
+        "find" => "Find files or search inside files\n\nUSAGE:\n    ez find PATTERN [PATH] [OPTIONS]\n\n    PATTERN is part of a name; use '*' to match everything\n\nOPTIONS:\n    --inside            Search inside file contents\n    -i, --ignore-case   Case insensitive\n    -n, --line-numbers  Show line numbers\n    --glob / --regex    Match names with a glob or regex\n    -t, --type TYPE     file, dir or symlink\n    -e, --ext EXTS      Only these extensions\n    --min-size/--max-size SIZE\n    --newer/--older AGE e.g. 2d, 3h or 2024-01-31\n    -d, --depth N       How deep to look\n    -H, --hidden        Include hidden files\n    --no-ignore         Don't skip .gitignored files\n\nACTIONS:\n    --exec CMD          Run CMD per match ({} is the path)\n    --exec-batch CMD    Run CMD with all matches (batched if too long)\n    --delete            Delete matches (asks first)\n    --copy-to DIR       Copy matches into DIR\n    --pack-into FILE    Pack matches into an archive\n    --chmod MODE        Change permissions of matches",
+        "search" => "Search inside files for text or regular expressions\n\nUSAGE:\n    ez search PATTERN [PATH] [OPTIONS]\n\nOPTIONS:\n    -c, --context N       Lines of context (default 2)\n    -i, --ignore-case     Case insensitive\n    -S, --smart-case      Case insensitive unless PATTERN has uppercase\n    -w, --word-regexp     Whole words only\n    -v, --invert-match    Show lines that don't match\n    -E, --regex           PATTERN is a regular expression\n    -U, --multiline       Match across lines\n    -l, --files-only      Only list matching files\n    --count               Matching lines per file\n    --include/--exclude GLOB\n    -z, --archives        Also search .gz/.bz2/.zip/.tar files\n    --indexed             Use the `ez index build` index to skip files",
+        "outline" | "symbols" => "List functions, types and headings with line numbers\n\nUSAGE:\n    ez outline [FILE|FOLDER]\n\nSupports Rust, Python, JavaScript/TypeScript, Go and Markdown.\nUse --json for each symbol's end line and signature.",
+        "todos" | "todo" => "Collect TODO/FIXME/HACK/XXX comments\n\nUSAGE:\n    ez todos [PATH] [OPTIONS]\n\nOPTIONS:\n    -t, --tag TAG         Look for TAG instead (repeatable)\n    -g, --group-by WHAT   file (default), tag or owner\n\nTODO(alice) and TODO(@alice) name an owner; #123 is an issue.",
+        "secrets" => "Find likely credentials before sharing a folder\n\nUSAGE:\n    ez secrets [PATH] [OPTIONS]\n\nOPTIONS:\n    --allowlist FILE   Entries to skip (default: PATH/.ez-secrets-allow)\n    --gitignore        Skip files git ignores\n\nRules: aws-access-key-id, aws-secret-access-key, private-key, jwt,\nenv-high-entropy. Exits with code 6 when anything is found.",
+        "index" => "Keep a trigram index so searches only read files that could match\n\nUSAGE:\n    ez index build [PATH]\n    ez search PATTERN [PATH] --indexed\n\nRe-running build only re-reads changed files. Searches fall back to\nreading everything when the index is out of date.",
         "copy" | "cp" => "Copy files or folders\n\nUSAGE:\n    ez copy FROM TO [OPTIONS]\n\nOPTIONS:\n    -r, --recursive   Copy folders recursively\n    -p, --progress    Show progress bar",
         "download" | "fetch" => "Download files from the internet\n\nUSAGE:\n    ez download URL [OPTIONS]\n\nOPTIONS:\n    -s, --save NAME   Save with specific filename\n    -p, --progress    Show progress bar",
         _ => return Err(EzError::NotFound(format!("No help available for '{}'", cmd))),
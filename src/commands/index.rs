@@ -0,0 +1,507 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, UNIX_EPOCH};
+use colored::*;
+use regex_syntax::hir::{Hir, HirKind};
+use crate::context::CommandContext;
+use crate::output::{CommandOutput, EzError};
+use crate::walk::WalkOptions;
+
+/// Written at the root of the indexed folder; skipped when searching.
+pub const INDEX_FILE: &str = ".ez-index";
+const MAGIC: &[u8] = b"EZTRIGRAM1\n";
+/// Counts read from disk only reserve up to this much up front, so a corrupt
+/// index fails on a short read instead of a huge allocation.
+const MAX_PREALLOC: usize = 64 * 1024;
+
+/// How both `ez index` and a plain `ez search` walk a folder, so an indexed
+/// search looks at exactly the files an unindexed one would.
+pub fn walk_options(threads: usize) -> WalkOptions {
+    WalkOptions { skip_git: true, threads, ..Default::default() }
+}
+
+/// The index itself and the temp files it's written through.
+pub fn is_index_file(name: &std::ffi::OsStr) -> bool {
+    name.as_bytes().starts_with(INDEX_FILE.as_bytes())
+}
+
+/// A file or folder as it was when indexed. Folder mtimes change when
+/// entries are added, removed or renamed, which is how new files are noticed.
+struct Record {
+    /// Relative to the index root
+    path: PathBuf,
+    mtime: (i64, u32),
+    size: u64,
+}
+
+/// Trigrams (three ASCII-lowercased bytes) mapped to the files containing them.
+pub struct Index {
+    pub root: PathBuf,
+    files: Vec<Record>,
+    dirs: Vec<Record>,
+    postings: HashMap<u32, Vec<u32>>,
+}
+
+pub fn build(path: PathBuf, threads: usize, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    let meta = fs::metadata(&path).map_err(|e| {
+        if e.kind() == io::ErrorKind::NotFound {
+            EzError::NotFound(format!("Cannot access '{}': {}", path.display(), e))
+        } else {
+            EzError::General(format!("Cannot access '{}': {}", path.display(), e))
+        }
+    })?;
+    if !meta.is_dir() {
+        return Err(EzError::InvalidArgs(format!("'{}' is not a folder", path.display())));
+    }
+    let index_path = path.join(INDEX_FILE);
+    let started = Instant::now();
+
+    // Files whose mtime and size haven't changed keep their trigrams from the last build
+    let previous = Index::load(&path).ok();
+    let mut reused: HashMap<&Path, (&Record, Vec<u32>)> = HashMap::new();
+    if let Some(old) = &previous {
+        let mut per_file: Vec<Vec<u32>> = vec![Vec::new(); old.files.len()];
+        for (&trigram, ids) in &old.postings {
+            for &id in ids {
+                per_file[id as usize].push(trigram);
+            }
+        }
+        for (record, trigrams) in old.files.iter().zip(per_file) {
+            reused.insert(record.path.as_path(), (record, trigrams));
+        }
+    }
+
+    enum Found {
+        Dir(Record),
+        File(Record, Option<Vec<u32>>),
+    }
+    let entries = crate::walk::walk(&path, &walk_options(threads), |entry| {
+        if is_index_file(entry.file_name()) {
+            return None;
+        }
+        let metadata = entry.metadata().ok()?;
+        let record = Record {
+            path: entry.path().strip_prefix(&path).ok()?.to_path_buf(),
+            mtime: mtime(&metadata),
+            size: metadata.len(),
+        };
+        if metadata.is_dir() {
+            return Some(Found::Dir(record));
+        }
+        if !metadata.is_file() {
+            return None;
+        }
+        let unchanged = reused.get(record.path.as_path())
+            .is_some_and(|(old, _)| old.mtime == record.mtime && old.size == record.size);
+        if unchanged {
+            return Some(Found::File(record, None));
+        }
+        let trigrams = file_trigrams(entry.path()).ok()?;
+        Some(Found::File(record, Some(trigrams)))
+    });
+
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    let mut postings: HashMap<u32, Vec<u32>> = HashMap::new();
+    let (mut added, mut updated, mut unchanged) = (0, 0, 0);
+    for (_, found) in entries {
+        let (record, trigrams) = match found {
+            Found::Dir(record) => {
+                dirs.push(record);
+                continue;
+            }
+            Found::File(record, Some(trigrams)) => {
+                if reused.contains_key(record.path.as_path()) {
+                    updated += 1;
+                } else {
+                    added += 1;
+                }
+                (record, trigrams)
+            }
+            Found::File(record, None) => {
+                unchanged += 1;
+                let trigrams = reused.get(record.path.as_path()).map(|(_, t)| t.clone()).unwrap_or_default();
+                (record, trigrams)
+            }
+        };
+        let id = files.len() as u32;
+        for trigram in trigrams {
+            postings.entry(trigram).or_default().push(id);
+        }
+        files.push(record);
+    }
+    let removed = previous.as_ref().map_or(0, |old| old.files.len()).saturating_sub(updated + unchanged);
+
+    let index = Index { root: path.clone(), files, dirs, postings };
+    index.save(&index_path)
+        .map_err(|e| EzError::General(format!("Cannot write '{}': {}", index_path.display(), e)))?;
+    let size = fs::metadata(&index_path).map(|m| m.len()).unwrap_or(0);
+
+    if !ctx.json {
+        println!("{} Indexed {} file(s) in '{}' ({:.1}s)",
+            "✓".green(), index.files.len(), path.display(), started.elapsed().as_secs_f64());
+        println!("  {} new, {} updated, {} removed, {} unchanged", added, updated, removed, unchanged);
+        println!("  Index: {} ({})", index_path.display().to_string().cyan(), crate::utils::format_size(size));
+        println!("  Search with: {}", "ez search PATTERN --indexed".dimmed());
+    }
+
+    Ok(CommandOutput::new("index", serde_json::json!({
+        "root": path.display().to_string(),
+        "index": index_path.display().to_string(),
+        "files": index.files.len(),
+        "added": added,
+        "updated": updated,
+        "removed": removed,
+        "unchanged": unchanged,
+        "trigrams": index.postings.len(),
+        "size": size,
+    })))
+}
+
+impl Index {
+    /// Nearest folder at or above `start` that has an index.
+    pub fn find_root(start: &Path) -> Option<PathBuf> {
+        let start = fs::canonicalize(start).ok()?;
+        start.ancestors().find(|dir| dir.join(INDEX_FILE).is_file()).map(Path::to_path_buf)
+    }
+
+    pub fn load(root: &Path) -> io::Result<Index> {
+        let mut r = BufReader::new(fs::File::open(root.join(INDEX_FILE))?);
+        let mut magic = vec![0; MAGIC.len()];
+        r.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not an ez index"));
+        }
+        let read_records = |r: &mut BufReader<fs::File>| -> io::Result<Vec<Record>> {
+            let count = read_u32(r)?;
+            let mut records = Vec::with_capacity((count as usize).min(MAX_PREALLOC));
+            for _ in 0..count {
+                let len = read_u32(r)? as u64;
+                let mut name = Vec::new();
+                if r.by_ref().take(len).read_to_end(&mut name)? as u64 != len {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                records.push(Record {
+                    path: PathBuf::from(std::ffi::OsStr::from_bytes(&name)),
+                    mtime: (read_u64(r)? as i64, read_u32(r)?),
+                    size: read_u64(r)?,
+                });
+            }
+            Ok(records)
+        };
+        let dirs = read_records(&mut r)?;
+        let files = read_records(&mut r)?;
+        let count = read_u32(&mut r)?;
+        let mut postings = HashMap::with_capacity((count as usize).min(MAX_PREALLOC));
+        for _ in 0..count {
+            let trigram = read_u32(&mut r)?;
+            let len = read_u32(&mut r)? as usize;
+            let mut ids = Vec::with_capacity(len.min(MAX_PREALLOC));
+            for _ in 0..len {
+                let id = read_u32(&mut r)?;
+                if id as usize >= files.len() {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "index lists a file that isn't in it"));
+                }
+                ids.push(id);
+            }
+            postings.insert(trigram, ids);
+        }
+        Ok(Index { root: root.to_path_buf(), files, dirs, postings })
+    }
+
+    /// Written to a temp file and renamed, so a crash never leaves half an index.
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let tmp = path.with_file_name(format!("{}.tmp-{}", INDEX_FILE, uuid::Uuid::new_v4()));
+        let result = (|| {
+            let mut w = BufWriter::new(fs::File::create(&tmp)?);
+            w.write_all(MAGIC)?;
+            for records in [&self.dirs, &self.files] {
+                w.write_all(&(records.len() as u32).to_le_bytes())?;
+                for record in records {
+                    let name = record.path.as_os_str().as_bytes();
+                    w.write_all(&(name.len() as u32).to_le_bytes())?;
+                    w.write_all(name)?;
+                    w.write_all(&(record.mtime.0 as u64).to_le_bytes())?;
+                    w.write_all(&record.mtime.1.to_le_bytes())?;
+                    w.write_all(&record.size.to_le_bytes())?;
+                }
+            }
+            let mut trigrams: Vec<_> = self.postings.iter().collect();
+            trigrams.sort_by_key(|(t, _)| **t);
+            w.write_all(&(trigrams.len() as u32).to_le_bytes())?;
+            for (trigram, ids) in trigrams {
+                w.write_all(&trigram.to_le_bytes())?;
+                w.write_all(&(ids.len() as u32).to_le_bytes())?;
+                for id in ids {
+                    w.write_all(&id.to_le_bytes())?;
+                }
+            }
+            w.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            fs::rename(&tmp, path)
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+            return result;
+        }
+
+        // Creating the index just changed the root folder's mtime. Overwriting the
+        // root's record in place (it's always the first folder) doesn't touch it again.
+        if self.dirs.first().is_some_and(|r| r.path.as_os_str().is_empty()) {
+            let root = fs::metadata(path.parent().unwrap_or(Path::new(".")))?;
+            let (secs, nanos) = mtime(&root);
+            let mut file = fs::OpenOptions::new().write(true).open(path)?;
+            file.seek(SeekFrom::Start(MAGIC.len() as u64 + 8))?;
+            file.write_all(&(secs as u64).to_le_bytes())?;
+            file.write_all(&nanos.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Every indexed file, relative to the root.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|r| r.path.as_path())
+    }
+
+    /// How many indexed files and folders no longer look the way they did at
+    /// build time. Any folder change means files may have been added or removed.
+    pub fn stale_count(&self, threads: usize) -> usize {
+        let records: HashMap<PathBuf, &Record> = self.dirs.iter().chain(&self.files)
+            .map(|r| (self.root.join(&r.path), r))
+            .collect();
+        let paths: Vec<PathBuf> = records.keys().cloned().collect();
+        crate::walk::map_paths(paths, threads, |path| {
+            let record = records[path];
+            let changed = match fs::symlink_metadata(path) {
+                Ok(m) if m.is_dir() => mtime(&m) != record.mtime,
+                Ok(m) => mtime(&m) != record.mtime || m.len() != record.size,
+                Err(_) => true,
+            };
+            changed.then_some(())
+        }).len()
+    }
+
+    /// Files (relative to the root) that could contain every one of `literals`.
+    /// Literals shorter than three bytes don't narrow anything down. With
+    /// `case_insensitive`, trigrams with non-ASCII bytes are skipped since their
+    /// other-case forms are different bytes.
+    pub fn candidates(&self, literals: &[Vec<u8>], case_insensitive: bool) -> Vec<&Path> {
+        let mut wanted: HashSet<u32> = HashSet::new();
+        for literal in literals {
+            for window in literal.windows(3) {
+                if case_insensitive && !window.is_ascii() {
+                    continue;
+                }
+                wanted.insert(trigram(window[0], window[1], window[2]));
+            }
+        }
+
+        let mut ids: Option<Vec<u32>> = None;
+        let mut lists: Vec<&Vec<u32>> = Vec::new();
+        for t in &wanted {
+            match self.postings.get(t) {
+                Some(list) => lists.push(list),
+                None => return Vec::new(),
+            }
+        }
+        // Intersect shortest lists first
+        lists.sort_by_key(|l| l.len());
+        for list in lists {
+            ids = Some(match ids {
+                None => list.clone(),
+                Some(current) => {
+                    let set: HashSet<u32> = list.iter().copied().collect();
+                    current.into_iter().filter(|id| set.contains(id)).collect()
+                }
+            });
+        }
+        match ids {
+            Some(ids) => ids.into_iter().map(|id| self.files[id as usize].path.as_path()).collect(),
+            None => self.files.iter().map(|r| r.path.as_path()).collect(),
+        }
+    }
+}
+
+/// Literal byte strings every match of `pattern` must contain, e.g. `foo.*bar`
+/// gives `foo` and `bar`. Alternations and classes contribute nothing, which
+/// just means less narrowing.
+pub fn required_literals(pattern: &str) -> Vec<Vec<u8>> {
+    let mut literals = Vec::new();
+    if let Ok(hir) = regex_syntax::ParserBuilder::new().build().parse(pattern) {
+        collect_literals(&hir, &mut literals);
+    }
+    literals
+}
+
+fn collect_literals(hir: &Hir, out: &mut Vec<Vec<u8>>) {
+    match hir.kind() {
+        HirKind::Literal(lit) => out.push(lit.0.to_vec()),
+        HirKind::Capture(capture) => collect_literals(&capture.sub, out),
+        HirKind::Repetition(rep) if rep.min >= 1 => collect_literals(&rep.sub, out),
+        HirKind::Concat(children) => {
+            let mut run = Vec::new();
+            for child in children {
+                match child.kind() {
+                    HirKind::Literal(lit) => run.extend_from_slice(&lit.0),
+                    // Zero-width, so neighbours stay adjacent
+                    HirKind::Look(_) => {}
+                    _ => {
+                        if !run.is_empty() {
+                            out.push(std::mem::take(&mut run));
+                        }
+                        collect_literals(child, out);
+                    }
+                }
+            }
+            if !run.is_empty() {
+                out.push(run);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn trigram(a: u8, b: u8, c: u8) -> u32 {
+    (a.to_ascii_lowercase() as u32) << 16 | (b.to_ascii_lowercase() as u32) << 8 | c.to_ascii_lowercase() as u32
+}
+
+/// Every trigram in a file, read in chunks so big files don't need to fit in memory.
+fn file_trigrams(path: &Path) -> io::Result<Vec<u32>> {
+    let mut file = fs::File::open(path)?;
+    let mut seen: HashSet<u32> = HashSet::new();
+    let mut buf = vec![0; 64 * 1024];
+    let mut carry: Vec<u8> = Vec::with_capacity(2);
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        let mut window = std::mem::take(&mut carry);
+        window.extend_from_slice(&buf[..n]);
+        for w in window.windows(3) {
+            seen.insert(trigram(w[0], w[1], w[2]));
+        }
+        carry = window[window.len().saturating_sub(2)..].to_vec();
+    }
+    let mut trigrams: Vec<u32> = seen.into_iter().collect();
+    trigrams.sort_unstable();
+    Ok(trigrams)
+}
+
+fn mtime(metadata: &fs::Metadata) -> (i64, u32) {
+    let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+    match modified.duration_since(UNIX_EPOCH) {
+        Ok(d) => (d.as_secs() as i64, d.subsec_nanos()),
+        Err(e) => (-(e.duration().as_secs() as i64), e.duration().subsec_nanos()),
+    }
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut b = [0; 4];
+    r.read_exact(&mut b)?;
+    Ok(u32::from_le_bytes(b))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut b = [0; 8];
+    r.read_exact(&mut b)?;
+    Ok(u64::from_le_bytes(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_quietly(dir: &Path) -> Index {
+        let ctx = CommandContext { json: true, yes: true, dry_run: false, is_tty: false, is_stdin_tty: false };
+        assert!(build(dir.to_path_buf(), 1, &ctx).is_ok());
+        Index::load(dir).unwrap()
+    }
+
+    fn literals(pattern: &str) -> Vec<String> {
+        required_literals(pattern).into_iter().map(|l| String::from_utf8(l).unwrap()).collect()
+    }
+
+    #[test]
+    fn required_literals_come_from_concatenations() {
+        assert_eq!(literals("hello"), ["hello"]);
+        assert_eq!(literals("foo.*bar"), ["foo", "bar"]);
+        assert_eq!(literals(r"\bfn (main)\("), ["fn ", "main", "("]);
+        assert_eq!(literals("(?:abc)+x"), ["abc", "x"]);
+    }
+
+    #[test]
+    fn optional_and_alternated_parts_give_no_literals() {
+        assert!(literals("foo|bar").is_empty());
+        assert!(literals("[abc]+").is_empty());
+        assert_eq!(literals("x(?:abc)?yz"), ["x", "yz"]);
+        assert!(literals("(").is_empty());
+    }
+
+    #[test]
+    fn candidates_are_files_with_every_trigram() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::write(dir.join("a.txt"), "fn main() {}").unwrap();
+        fs::write(dir.join("b.txt"), "fn helper() {}").unwrap();
+        fs::write(dir.join("c.txt"), "MAIN_LOOP").unwrap();
+        let index = build_quietly(dir);
+
+        let find = |literals: &[&str], ci: bool| {
+            let literals: Vec<Vec<u8>> = literals.iter().map(|l| l.as_bytes().to_vec()).collect();
+            let mut found: Vec<String> = index.candidates(&literals, ci).iter().map(|p| p.display().to_string()).collect();
+            found.sort();
+            found
+        };
+        assert_eq!(find(&["main"], false), ["a.txt", "c.txt"]);
+        assert_eq!(find(&["fn ", "main"], false), ["a.txt"]);
+        assert!(find(&["nowhere"], false).is_empty());
+        // Too short to narrow anything down
+        assert_eq!(find(&["fn"], false), ["a.txt", "b.txt", "c.txt"]);
+    }
+
+    #[test]
+    fn index_is_fresh_after_build_and_stale_after_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join("a.txt"), "one").unwrap();
+        let index = build_quietly(dir);
+        assert_eq!(index.file_count(), 1);
+        assert_eq!(index.stale_count(1), 0);
+
+        // Git's own bookkeeping isn't indexed, so it can't make the index stale
+        fs::write(dir.join(".git").join("index"), "changed").unwrap();
+        assert_eq!(index.stale_count(1), 0);
+
+        fs::write(dir.join("b.txt"), "two").unwrap();
+        assert_eq!(index.stale_count(1), 1);
+        let index = build_quietly(dir);
+        assert_eq!(index.file_count(), 2);
+        assert_eq!(index.stale_count(1), 0);
+    }
+
+    #[test]
+    fn load_rejects_postings_for_unknown_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut postings = HashMap::new();
+        postings.insert(trigram(b'a', b'b', b'c'), vec![5]);
+        let index = Index { root: dir.to_path_buf(), files: Vec::new(), dirs: Vec::new(), postings };
+        index.save(&dir.join(INDEX_FILE)).unwrap();
+        let err = Index::load(dir).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A huge count with nothing behind it is a short read, not a huge allocation
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(dir.join(INDEX_FILE), bytes).unwrap();
+        assert_eq!(Index::load(dir).err().unwrap().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
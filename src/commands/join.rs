@@ -0,0 +1,178 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use colored::*;
+use sha2::{Digest, Sha256};
+use crate::commands::split::Manifest;
+use crate::context::CommandContext;
+use crate::output::{CommandOutput, EzError};
+
+pub fn execute(manifest_path: PathBuf, output: Option<PathBuf>, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    let text = fs::read_to_string(&manifest_path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            EzError::NotFound(format!("Cannot read '{}': {}", manifest_path.display(), e))
+        } else {
+            EzError::General(format!("Cannot read '{}': {}", manifest_path.display(), e))
+        }
+    })?;
+    let manifest: Manifest = serde_json::from_str(&text).map_err(|e| {
+        EzError::InvalidArgs(format!("'{}' is not an ez split manifest: {}", manifest_path.display(), e))
+    })?;
+
+    // Names come from a file anyone could have edited; `../x` or `/etc/x` would read or write elsewhere
+    for name in std::iter::once(&manifest.file).chain(manifest.chunks.iter().map(|c| &c.name)) {
+        if !is_plain_name(name) {
+            return Err(EzError::InvalidArgs(format!(
+                "'{}' lists '{}', which is not a plain file name", manifest_path.display(), name
+            )));
+        }
+    }
+
+    // Chunks are looked up next to the manifest, wherever it has been moved to
+    let dir = manifest_path.parent().unwrap_or(Path::new("")).to_path_buf();
+    let output = output.unwrap_or_else(|| dir.join(&manifest.file));
+
+    let mut missing = Vec::new();
+    for chunk in &manifest.chunks {
+        let path = dir.join(&chunk.name);
+        match fs::metadata(&path) {
+            Ok(m) if m.len() != chunk.size => {
+                return Err(EzError::General(format!(
+                    "Chunk '{}' is {} bytes but should be {}. It may be incomplete", path.display(), m.len(), chunk.size
+                )));
+            }
+            Ok(_) => {}
+            Err(_) => missing.push(path.display().to_string()),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(EzError::NotFound(format!("Missing chunk(s): {}", missing.join(", "))));
+    }
+
+    if output.exists() {
+        if ctx.should_confirm() && !ctx.dry_run {
+            print!("'{}' already exists. Overwrite? [y/N] ", output.display());
+            std::io::stdout().flush().unwrap();
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input).unwrap();
+            if !input.trim().eq_ignore_ascii_case("y") {
+                return Err(EzError::Cancelled("Join cancelled by user".to_string()));
+            }
+        } else if !ctx.yes && !ctx.dry_run {
+            return Err(EzError::InvalidArgs(format!(
+                "'{}' already exists. Use --output to write elsewhere or --yes to overwrite", output.display()
+            )));
+        }
+    }
+
+    if ctx.dry_run {
+        if !ctx.json {
+            println!("{} Would join {} chunk(s) into '{}' ({})",
+                "~".dimmed(), manifest.chunks.len(), output.display(), crate::utils::format_size(manifest.size));
+        }
+        return Ok(CommandOutput::new("join", serde_json::json!({
+            "output": output.display().to_string(),
+            "chunks": manifest.chunks.len(),
+            "size": manifest.size,
+        })).with_metadata(serde_json::json!({ "dry_run": true })));
+    }
+
+    // Build into a temporary file so a bad chunk never leaves a half-joined output behind
+    let name = output.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let tmp = output.with_file_name(format!(".{}.ez-join-{}", name, uuid::Uuid::new_v4()));
+    let result = join_into(&manifest, &dir, &tmp, ctx)
+        .and_then(|_| fs::rename(&tmp, &output)
+            .map_err(|e| EzError::General(format!("Cannot write '{}': {}", output.display(), e))));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+
+    if !ctx.json {
+        println!("{} Joined {} chunk(s) into '{}' ({})",
+            "✓".green(), manifest.chunks.len(), output.display().to_string().cyan(), crate::utils::format_size(manifest.size));
+        println!("  {} SHA-256 verified: {}", "✓".green(), manifest.sha256.dimmed());
+    }
+
+    Ok(CommandOutput::new("join", serde_json::json!({
+        "output": output.display().to_string(),
+        "chunks": manifest.chunks.len(),
+        "size": manifest.size,
+        "sha256": manifest.sha256,
+    })).with_metadata(serde_json::json!({ "verified": true })))
+}
+
+fn join_into(manifest: &Manifest, dir: &Path, tmp: &Path, ctx: &CommandContext) -> Result<(), EzError> {
+    let mut out = fs::File::create(tmp)
+        .map_err(|e| EzError::General(format!("Cannot create '{}': {}", tmp.display(), e)))?;
+    let pb = if ctx.json { None } else { Some(crate::utils::byte_progress_bar(manifest.size)) };
+    let mut whole = Sha256::new();
+    let mut buffer = vec![0; 65536];
+
+    for chunk in &manifest.chunks {
+        let path = dir.join(&chunk.name);
+        let mut input = fs::File::open(&path)
+            .map_err(|e| EzError::General(format!("Cannot read '{}': {}", path.display(), e)))?;
+        let mut hasher = Sha256::new();
+        loop {
+            let n = input.read(&mut buffer)
+                .map_err(|e| EzError::General(format!("Cannot read '{}': {}", path.display(), e)))?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buffer[..n]);
+            whole.update(&buffer[..n]);
+            out.write_all(&buffer[..n])
+                .map_err(|e| EzError::General(format!("Cannot write '{}': {}", tmp.display(), e)))?;
+            if let Some(pb) = &pb {
+                pb.inc(n as u64);
+            }
+        }
+
+        let actual = format!("{:x}", hasher.finalize());
+        if actual != chunk.sha256 {
+            if let Some(pb) = &pb {
+                pb.abandon();
+            }
+            return Err(EzError::General(format!(
+                "Chunk '{}' is corrupted (expected SHA-256 {}, got {})", path.display(), chunk.sha256, actual
+            )));
+        }
+    }
+    if let Some(pb) = pb {
+        pb.finish_and_clear();
+    }
+
+    let actual = format!("{:x}", whole.finalize());
+    if actual != manifest.sha256 {
+        return Err(EzError::General(format!(
+            "Joined file does not match the original (expected SHA-256 {}, got {})", manifest.sha256, actual
+        )));
+    }
+    out.sync_all().map_err(|e| EzError::General(format!("Cannot write '{}': {}", tmp.display(), e)))
+}
+
+/// A single file name, with no folders, `..` or root in it.
+fn is_plain_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_names_are_accepted() {
+        assert!(is_plain_name("video.mp4"));
+        assert!(is_plain_name("video.mp4.part001"));
+        assert!(is_plain_name(".hidden"));
+    }
+
+    #[test]
+    fn paths_in_manifest_names_are_rejected() {
+        for name in ["", ".", "..", "../.bashrc", "../../etc/passwd", "/etc/passwd", "sub/chunk", "./../x"] {
+            assert!(!is_plain_name(name), "{:?} should be rejected", name);
+        }
+    }
+}
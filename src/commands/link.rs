@@ -0,0 +1,389 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use colored::*;
+use walkdir::WalkDir;
+use crate::context::CommandContext;
+use crate::output::{CommandOutput, EzError};
+
+pub fn execute(target: PathBuf, name: PathBuf, hard: bool, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    // Like ln: linking into an existing folder keeps the target's name
+    let name = if name.is_dir() && !name.is_symlink() {
+        match target.file_name() {
+            Some(file_name) => name.join(file_name),
+            None => return Err(EzError::InvalidArgs(format!("Cannot tell what to call the link to '{}'", target.display()))),
+        }
+    } else {
+        name
+    };
+
+    if name.symlink_metadata().is_ok() {
+        return Err(EzError::InvalidArgs(format!("'{}' already exists", name.display())));
+    }
+
+    // A relative symlink target is resolved from the link's folder, not from here
+    let resolved = if hard || target.is_absolute() {
+        target.clone()
+    } else {
+        name.parent().unwrap_or(Path::new("")).join(&target)
+    };
+    let target_exists = resolved.exists();
+
+    if hard {
+        if !target_exists {
+            return Err(EzError::NotFound(format!("'{}' does not exist", target.display())));
+        }
+        if target.is_dir() {
+            return Err(EzError::InvalidArgs(format!(
+                "'{}' is a folder, and hard links can only point to files. Drop --hard to make a symlink", target.display()
+            )));
+        }
+    }
+
+    let kind = if hard { "hard link" } else { "symlink" };
+    let mut warnings = Vec::new();
+    if !hard && !target_exists {
+        warnings.push(format!(
+            "'{}' does not exist (looked for it at '{}'), so the link will be broken until it does",
+            target.display(), resolved.display()
+        ));
+    }
+
+    if !ctx.dry_run {
+        let result = if hard {
+            fs::hard_link(&target, &name)
+        } else {
+            std::os::unix::fs::symlink(&target, &name)
+        };
+        result.map_err(|e| {
+            if e.raw_os_error() == Some(libc::EXDEV) {
+                EzError::InvalidArgs(format!(
+                    "'{}' and '{}' are on different drives; hard links must stay on one. Drop --hard to make a symlink",
+                    target.display(), name.display()
+                ))
+            } else if e.kind() == std::io::ErrorKind::PermissionDenied {
+                EzError::PermissionDenied(format!("Cannot create {} '{}': {}", kind, name.display(), e))
+            } else {
+                EzError::General(format!("Cannot create {} '{}': {}", kind, name.display(), e))
+            }
+        })?;
+    }
+
+    let explanation = if hard {
+        format!(
+            "'{}' and '{}' are now two names for the same file. Changes through either show up in both, \
+             and the data stays until both are deleted",
+            name.display(), target.display()
+        )
+    } else {
+        format!(
+            "'{}' is a shortcut that points to '{}'. Opening it opens the target; \
+             if the target is moved or deleted, the link breaks",
+            name.display(), target.display()
+        )
+    };
+
+    if !ctx.json {
+        let (mark, verb) = if ctx.dry_run { ("~".dimmed(), "Would create") } else { ("✓".green(), "Created") };
+        println!("{} {} {} {} → {}", mark, verb, kind, name.display().to_string().cyan(), target.display());
+        println!("  {}", explanation.dimmed());
+        for warning in &warnings {
+            println!("{} {}", "⚠️".yellow(), warning.yellow());
+        }
+    }
+
+    let output = CommandOutput::new("link", serde_json::json!({
+        "name": name.display().to_string(),
+        "target": target.display().to_string(),
+        "type": if hard { "hard" } else { "symbolic" },
+        "target_exists": target_exists,
+        "explanation": explanation,
+        "warnings": warnings,
+    }));
+    Ok(if ctx.dry_run {
+        output.with_metadata(serde_json::json!({ "dry_run": true }))
+    } else {
+        output
+    })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Problem {
+    Broken,
+    Cycle,
+    Outside,
+    AbsoluteInside,
+}
+
+impl Problem {
+    fn name(self) -> &'static str {
+        match self {
+            Problem::Broken => "broken",
+            Problem::Cycle => "cycle",
+            Problem::Outside => "outside",
+            Problem::AbsoluteInside => "absolute",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Problem::Broken => "target does not exist",
+            Problem::Cycle => "links loop back on themselves",
+            Problem::Outside => "points outside this folder",
+            Problem::AbsoluteInside => "absolute path; breaks if the folder is moved",
+        }
+    }
+}
+
+struct Finding {
+    path: PathBuf,
+    target: PathBuf,
+    problem: Problem,
+    /// Replacement target for --fix, when one can be worked out.
+    fix: Option<PathBuf>,
+}
+
+/// Audit the symlinks under `dir`. `--fix` repoints what it can (absolute links
+/// inside the tree become relative, broken links go to the only file with that
+/// name); `--remove` deletes broken and looping links.
+pub fn check(dir: PathBuf, fix: bool, remove: bool, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    let root = fs::canonicalize(&dir).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            EzError::NotFound(format!("Cannot access '{}': {}", dir.display(), e))
+        } else {
+            EzError::General(format!("Cannot access '{}': {}", dir.display(), e))
+        }
+    })?;
+
+    let mut links = Vec::new();
+    let mut by_name: std::collections::HashMap<std::ffi::OsString, Vec<PathBuf>> = std::collections::HashMap::new();
+    for entry in WalkDir::new(&dir).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+        if entry.file_type().is_symlink() {
+            links.push(entry.path().to_path_buf());
+        } else if entry.file_type().is_file() {
+            by_name.entry(entry.file_name().to_os_string()).or_default().push(entry.path().to_path_buf());
+        }
+    }
+
+    let mut findings = Vec::new();
+    for link in &links {
+        let Ok(target) = fs::read_link(link) else { continue };
+        let parent = link.parent().unwrap_or(Path::new(""));
+
+        let problem = match fs::metadata(link) {
+            Err(e) if e.raw_os_error() == Some(libc::ELOOP) => Problem::Cycle,
+            Err(_) => Problem::Broken,
+            // Relative links like ../../etc/passwd can leave the folder too
+            Ok(_) => match fs::canonicalize(link) {
+                Ok(resolved) if !resolved.starts_with(&root) => Problem::Outside,
+                _ if target.is_absolute() => Problem::AbsoluteInside,
+                _ => continue,
+            },
+        };
+
+        let fix = match problem {
+            Problem::AbsoluteInside => {
+                let abs_parent = fs::canonicalize(parent).unwrap_or_else(|_| parent.to_path_buf());
+                Some(relative_path(&abs_parent, &fs::canonicalize(link).unwrap_or(target.clone())))
+            }
+            Problem::Broken => target.file_name()
+                .and_then(|n| by_name.get(n))
+                .filter(|candidates| candidates.len() == 1)
+                .map(|candidates| {
+                    let abs_parent = fs::canonicalize(parent).unwrap_or_else(|_| parent.to_path_buf());
+                    let abs_candidate = fs::canonicalize(&candidates[0]).unwrap_or_else(|_| candidates[0].clone());
+                    relative_path(&abs_parent, &abs_candidate)
+                }),
+            _ => None,
+        };
+
+        findings.push(Finding { path: link.clone(), target, problem, fix });
+    }
+
+    let removable: Vec<&Finding> = findings.iter()
+        .filter(|f| remove && matches!(f.problem, Problem::Broken | Problem::Cycle) && !(fix && f.fix.is_some()))
+        .collect();
+
+    if !ctx.dry_run && !removable.is_empty() && ctx.should_confirm() {
+        print!("Remove {} broken or looping link(s)? [y/N] ", removable.len());
+        std::io::stdout().flush().unwrap();
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+        if !input.trim().eq_ignore_ascii_case("y") {
+            return Err(EzError::Cancelled("Link removal cancelled by user".to_string()));
+        }
+    } else if !ctx.dry_run && !removable.is_empty() && !ctx.yes {
+        return Err(EzError::Cancelled("Cannot confirm link removal in non-interactive mode. Use --yes to remove them".to_string()));
+    }
+
+    let mut results = Vec::new();
+    for finding in &findings {
+        let action = if let (true, Some(new_target)) = (fix, &finding.fix) {
+            if !ctx.dry_run {
+                repoint(&finding.path, new_target)?;
+            }
+            Some("fixed")
+        } else if removable.iter().any(|r| r.path == finding.path) {
+            if !ctx.dry_run {
+                fs::remove_file(&finding.path)
+                    .map_err(|e| EzError::General(format!("Cannot remove '{}': {}", finding.path.display(), e)))?;
+            }
+            Some("removed")
+        } else {
+            None
+        };
+
+        if !ctx.json {
+            let label = match finding.problem {
+                Problem::Broken | Problem::Cycle => finding.problem.name().red(),
+                _ => finding.problem.name().yellow(),
+            };
+            println!("{:>10}  {} → {}  {}", label, finding.path.display().to_string().cyan(),
+                finding.target.display(), format!("({})", finding.problem.describe()).dimmed());
+            match (action, &finding.fix) {
+                (Some("fixed"), Some(new_target)) => {
+                    let verb = if ctx.dry_run { "would repoint to" } else { "repointed to" };
+                    println!("{:>10}  {} {}", "", verb.green(), new_target.display());
+                }
+                (Some(_), _) => {
+                    let verb = if ctx.dry_run { "would remove" } else { "removed" };
+                    println!("{:>10}  {}", "", verb.green());
+                }
+                (None, Some(new_target)) => {
+                    println!("{:>10}  {} {}", "", "--fix would repoint to".dimmed(), new_target.display());
+                }
+                (None, None) => {}
+            }
+        }
+
+        let mut entry = serde_json::json!({
+            "path": finding.path.display().to_string(),
+            "target": finding.target.display().to_string(),
+            "problem": finding.problem.name(),
+            "description": finding.problem.describe(),
+            "suggested_target": finding.fix.as_ref().map(|p| p.display().to_string()),
+        });
+        if let Some(action) = action {
+            entry["action"] = serde_json::json!(action);
+        }
+        results.push(entry);
+    }
+
+    let count = |p: Problem| findings.iter().filter(|f| f.problem == p).count();
+    if !ctx.json {
+        if findings.is_empty() {
+            println!("{} Checked {} link(s) in '{}': all fine", "✓".green(), links.len(), dir.display());
+        } else {
+            println!("\n{} Checked {} link(s): {} broken, {} looping, {} outside the folder, {} absolute inside the folder",
+                "ℹ️".yellow(), links.len(), count(Problem::Broken), count(Problem::Cycle),
+                count(Problem::Outside), count(Problem::AbsoluteInside));
+            if !fix && !remove {
+                println!("  Use {} to repoint links or {} to delete broken ones", "--fix".cyan(), "--remove".cyan());
+            }
+        }
+    }
+
+    let mut metadata = serde_json::json!({
+        "checked": links.len(),
+        "broken": count(Problem::Broken),
+        "cycles": count(Problem::Cycle),
+        "outside": count(Problem::Outside),
+        "absolute": count(Problem::AbsoluteInside),
+    });
+    if ctx.dry_run {
+        metadata["dry_run"] = serde_json::json!(true);
+    }
+
+    Ok(CommandOutput::new("link", serde_json::json!({
+        "path": dir.display().to_string(),
+        "issues": results,
+    })).with_metadata(metadata))
+}
+
+/// Replace a symlink's target via a temporary link and rename, so it's never missing.
+fn repoint(link: &Path, new_target: &Path) -> Result<(), EzError> {
+    let name = link.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let tmp = link.with_file_name(format!(".{}.ez-link-{}", name, uuid::Uuid::new_v4()));
+    std::os::unix::fs::symlink(new_target, &tmp)
+        .and_then(|_| fs::rename(&tmp, link))
+        .map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            EzError::General(format!("Cannot repoint '{}': {}", link.display(), e))
+        })
+}
+
+/// Path to `to` as seen from folder `from`. Both must be absolute.
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut result = PathBuf::new();
+    for _ in common..from.len() {
+        result.push("..");
+    }
+    for part in &to[common..] {
+        result.push(part);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    fn ctx(yes: bool) -> CommandContext {
+        CommandContext { json: true, yes, dry_run: false, is_tty: false, is_stdin_tty: false }
+    }
+
+    fn problems(dir: &Path) -> Vec<(String, String)> {
+        let Ok(output) = check(dir.to_path_buf(), false, false, &ctx(false)) else { panic!("check failed") };
+        output.data["issues"].as_array().unwrap().iter()
+            .map(|i| (i["path"].as_str().unwrap().rsplit('/').next().unwrap().to_string(), i["problem"].as_str().unwrap().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn links_are_classified() {
+        let outer = tempfile::tempdir().unwrap();
+        let dir = outer.path().join("tree");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(outer.path().join("secret"), "x").unwrap();
+        fs::write(dir.join("real.txt"), "x").unwrap();
+
+        symlink("real.txt", dir.join("fine")).unwrap();
+        symlink(dir.join("real.txt"), dir.join("absolute")).unwrap();
+        symlink("../../secret", dir.join("sub/escape")).unwrap();
+        symlink(outer.path().join("secret"), dir.join("outside")).unwrap();
+        symlink("gone.txt", dir.join("broken")).unwrap();
+        symlink("loop", dir.join("loop")).unwrap();
+
+        assert_eq!(problems(&dir), [
+            ("absolute".to_string(), "absolute".to_string()),
+            ("broken".to_string(), "broken".to_string()),
+            ("loop".to_string(), "cycle".to_string()),
+            ("outside".to_string(), "outside".to_string()),
+            ("escape".to_string(), "outside".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn removing_without_a_terminal_needs_yes() {
+        let dir = tempfile::tempdir().unwrap();
+        symlink("gone.txt", dir.path().join("broken")).unwrap();
+
+        let refused = check(dir.path().to_path_buf(), false, true, &ctx(false));
+        assert!(matches!(refused, Err(EzError::Cancelled(_))));
+        assert!(dir.path().join("broken").symlink_metadata().is_ok());
+
+        assert!(check(dir.path().to_path_buf(), false, true, &ctx(true)).is_ok());
+        assert!(dir.path().join("broken").symlink_metadata().is_err());
+    }
+
+    #[test]
+    fn relative_paths_between_folders() {
+        assert_eq!(relative_path(Path::new("/a/b"), Path::new("/a/c/d")), PathBuf::from("../c/d"));
+        assert_eq!(relative_path(Path::new("/a"), Path::new("/a/b")), PathBuf::from("b"));
+    }
+}
@@ -1,22 +1,101 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::os::unix::fs::PermissionsExt;
 use colored::*;
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
 
-pub fn execute(file: PathBuf, if_not_exists: bool, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
-    let metadata = fs::metadata(&file).map_err(|e| {
+/// Interpreter line to suggest for a script extension.
+fn shebang_for(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    Some(match ext.as_str() {
+        "py" => "#!/usr/bin/env python3",
+        "sh" | "bash" => "#!/usr/bin/env bash",
+        "zsh" => "#!/usr/bin/env zsh",
+        "rb" => "#!/usr/bin/env ruby",
+        "js" | "mjs" | "cjs" => "#!/usr/bin/env node",
+        "pl" => "#!/usr/bin/env perl",
+        "php" => "#!/usr/bin/env php",
+        _ => return None,
+    })
+}
+
+pub fn execute(path: PathBuf, if_not_exists: bool, add_shebang: bool, all: bool, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    let metadata = fs::metadata(&path).map_err(|e| {
         if e.kind() == std::io::ErrorKind::NotFound {
-            EzError::NotFound(format!("Cannot access '{}': {}", file.display(), e))
+            EzError::NotFound(format!("Cannot access '{}': {}", path.display(), e))
         } else if e.kind() == std::io::ErrorKind::PermissionDenied {
-            EzError::PermissionDenied(format!("Cannot access '{}': {}", file.display(), e))
+            EzError::PermissionDenied(format!("Cannot access '{}': {}", path.display(), e))
         } else {
-            EzError::General(format!("Cannot access '{}': {}", file.display(), e))
+            EzError::General(format!("Cannot access '{}': {}", path.display(), e))
         }
     })?;
 
-    let mut permissions = metadata.permissions();
+    if !metadata.is_dir() {
+        let result = make_runnable(&path, if_not_exists, add_shebang, ctx)?;
+        let skipped = result["already_executable"] == serde_json::json!(true);
+        let output = CommandOutput::new("make-runnable", result);
+        return Ok(if skipped {
+            output.with_metadata(serde_json::json!({ "skipped": true }))
+        } else {
+            output
+        });
+    }
+
+    if !all {
+        return Err(EzError::InvalidArgs(format!(
+            "'{}' is a folder. Use --all to make every script inside it runnable", path.display()
+        )));
+    }
+
+    // Scripts are files with a known extension or one that already starts with #!
+    let mut scripts: Vec<PathBuf> = fs::read_dir(&path)
+        .map_err(|e| EzError::General(format!("Cannot read '{}': {}", path.display(), e)))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && (shebang_for(p).is_some() || read_head(p).starts_with(b"#!")))
+        .collect();
+    scripts.sort();
+
+    let mut results = Vec::new();
+    for script in &scripts {
+        results.push(make_runnable(script, if_not_exists, add_shebang, ctx)?);
+    }
+
+    if !ctx.json {
+        if scripts.is_empty() {
+            println!("{} No scripts found in '{}'", "ℹ️".yellow(), path.display());
+        } else {
+            let (mark, verb) = if ctx.dry_run { ("~".dimmed(), "Would check") } else { ("✓".green(), "Checked") };
+            println!("\n{} {} {} script(s) in '{}'", mark, verb, scripts.len(), path.display());
+        }
+    }
+
+    let mut metadata = serde_json::json!({ "total": results.len() });
+    if ctx.dry_run {
+        metadata["dry_run"] = serde_json::json!(true);
+    }
+    Ok(CommandOutput::new("make-runnable", serde_json::json!({
+        "folder": path.display().to_string(),
+        "files": results,
+    })).with_metadata(metadata))
+}
+
+fn read_head(path: &Path) -> Vec<u8> {
+    use std::io::Read;
+    let mut head = Vec::new();
+    if let Ok(file) = fs::File::open(path) {
+        let _ = file.take(512).read_to_end(&mut head);
+    }
+    head
+}
+
+/// Check the shebang of one file, fix what can be fixed and set the execute bits.
+fn make_runnable(file: &Path, if_not_exists: bool, add_shebang: bool, ctx: &CommandContext) -> Result<serde_json::Value, EzError> {
+    let mut permissions = fs::metadata(file)
+        .map_err(|e| EzError::General(format!("Cannot access '{}': {}", file.display(), e)))?
+        .permissions();
     let current_mode = permissions.mode();
 
     // Check if already executable
@@ -24,25 +103,109 @@ pub fn execute(file: PathBuf, if_not_exists: bool, ctx: &CommandContext) -> Resu
         if !ctx.json {
             println!("{} Skipped '{}' (already executable)", "~".dimmed(), file.display());
         }
-        return Ok(CommandOutput::new("make-runnable", serde_json::json!({
+        return Ok(serde_json::json!({
             "file": file.display().to_string(),
             "already_executable": true,
-        })).with_metadata(serde_json::json!({ "skipped": true })));
+        }));
     }
 
-    let new_mode = current_mode | 0o111;
-    permissions.set_mode(new_mode);
+    let mut warnings = Vec::new();
+    let mut added_shebang = None;
+    let mut fixed_crlf = false;
 
-    fs::set_permissions(&file, permissions).map_err(|e| {
-        EzError::PermissionDenied(format!("Cannot change permissions: {}", e))
+    let bytes = fs::read(file).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            EzError::PermissionDenied(format!("Cannot read '{}': {}", file.display(), e))
+        } else {
+            EzError::General(format!("Cannot read '{}': {}", file.display(), e))
+        }
     })?;
 
+    // Compiled programs need no shebang; only look inside text files
+    let mut contents = if crate::utils::is_binary(&bytes) { None } else { String::from_utf8(bytes).ok() };
+    let mut shebang = None;
+
+    if let Some(text) = contents.as_mut() {
+        // Not lines(): it would hide the \r we are looking for
+        let first_line = text.split('\n').next().unwrap_or("");
+        if first_line.starts_with("#!") {
+            // "python3\r" is not a program: the kernel keeps the \r in the interpreter name
+            if first_line.ends_with('\r') {
+                let end = first_line.len();
+                text.remove(end - 1);
+                fixed_crlf = true;
+            }
+            shebang = text.split('\n').next().map(str::to_string);
+            if text.contains("\r\n") {
+                warnings.push("File uses Windows (CRLF) line endings, which may break the interpreter".to_string());
+            }
+        } else if let Some(suggested) = shebang_for(file) {
+            let add = add_shebang || ctx.yes || (ctx.should_confirm() && !ctx.json && !ctx.dry_run && confirm(file, suggested));
+            if add {
+                // Always \n, even in a CRLF file: a \r would end up in the interpreter name
+                text.insert_str(0, &format!("{}\n", suggested));
+                added_shebang = Some(suggested);
+                shebang = Some(suggested.to_string());
+            } else {
+                warnings.push(format!("No shebang line; running it directly will fail. Add one with --add-shebang ({})", suggested));
+            }
+        } else {
+            warnings.push("No shebang line, and the interpreter can't be guessed from the extension".to_string());
+        }
+    }
+
+    if added_shebang.is_none() && !fixed_crlf {
+        contents = None;
+    }
+
+    let new_mode = current_mode | 0o111;
+
+    if !ctx.dry_run {
+        if let Some(text) = &contents {
+            crate::utils::write_atomic(file, text.as_bytes(), None).map_err(|e| {
+                if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    EzError::PermissionDenied(format!("Cannot write to '{}': {}", file.display(), e))
+                } else {
+                    EzError::General(format!("Cannot write to '{}': {}", file.display(), e))
+                }
+            })?;
+        }
+        permissions.set_mode(new_mode);
+        fs::set_permissions(file, permissions).map_err(|e| {
+            EzError::PermissionDenied(format!("Cannot change permissions: {}", e))
+        })?;
+    }
+
     if !ctx.json {
-        println!("{} Made '{}' executable", "✓".green(), file.display().to_string().cyan());
+        let (mark, verb) = if ctx.dry_run { ("~".dimmed(), "Would make") } else { ("✓".green(), "Made") };
+        println!("{} {} '{}' executable", mark, verb, file.display().to_string().cyan());
+        if let Some(line) = added_shebang {
+            let verb = if ctx.dry_run { "Would add" } else { "Added" };
+            println!("  {} {} {}", "+".green(), verb, line.green());
+        }
+        if fixed_crlf {
+            let verb = if ctx.dry_run { "Would remove" } else { "Removed" };
+            println!("  {} {} the Windows line ending (\\r) from the shebang line", "+".green(), verb);
+        }
+        for warning in &warnings {
+            println!("  {} {}", "⚠️".yellow(), warning.yellow());
+        }
     }
 
-    Ok(CommandOutput::new("make-runnable", serde_json::json!({
+    Ok(serde_json::json!({
         "file": file.display().to_string(),
         "mode": format!("{:o}", new_mode & 0o777),
-    })))
+        "shebang": shebang,
+        "added_shebang": added_shebang.is_some(),
+        "fixed_crlf": fixed_crlf,
+        "warnings": warnings,
+    }))
+}
+
+fn confirm(file: &Path, shebang: &str) -> bool {
+    print!("'{}' has no shebang line. Add '{}'? [y/N] ", file.display(), shebang);
+    std::io::stdout().flush().unwrap();
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input).unwrap();
+    input.trim().eq_ignore_ascii_case("y")
 }
@@ -1,21 +1,27 @@
 pub mod chain;
+pub mod config_edit;
 pub mod copy;
 pub mod create_file;
 pub mod create_folder;
 pub mod disk;
+pub mod edit;
 pub mod env;
 pub mod explain;
 pub mod find;
 pub mod help_me;
+pub mod link;
 pub mod list;
 pub mod r#move;
 pub mod network;
+pub mod normalize;
 pub mod peek;
 pub mod permissions;
 pub mod ports;
 pub mod remove;
+pub mod rename;
 pub mod replace;
 pub mod r#where;
+pub mod scaffold;
 pub mod search;
 pub mod show;
 pub mod size;
@@ -24,6 +30,8 @@ pub mod stop;
 pub mod download;
 pub mod pack;
 pub mod unpack;
+pub mod split;
+pub mod join;
 pub mod space;
 pub mod count;
 pub mod sort;
@@ -32,3 +40,7 @@ pub mod make_runnable;
 pub mod tree;
 pub mod watch;
 pub mod schema;
+pub mod index;
+pub mod outline;
+pub mod todos;
+pub mod secrets;
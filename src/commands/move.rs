@@ -1,6 +1,9 @@
 use std::fs;
-use std::path::PathBuf;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
 use colored::*;
+use walkdir::WalkDir;
+use crate::commands::copy::{self, CopyStats};
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
 
@@ -20,22 +23,53 @@ pub fn execute(from: PathBuf, to: PathBuf, if_not_exists: bool, ctx: &CommandCon
     }
 
     if ctx.dry_run {
+        let cross_device = is_cross_device(&from, &to);
         if !ctx.json {
-            println!("{} Would move '{}' to '{}'", "~".dimmed(), from.display(), to.display());
+            if cross_device {
+                println!("{} Would move '{}' to '{}' (different filesystem: copy, verify, then remove original)",
+                    "~".dimmed(), from.display(), to.display());
+            } else {
+                println!("{} Would move '{}' to '{}'", "~".dimmed(), from.display(), to.display());
+            }
         }
         return Ok(CommandOutput::new("move", serde_json::json!({
             "from": from.display().to_string(),
             "to": to.display().to_string(),
-        })).with_metadata(serde_json::json!({ "dry_run": true })));
+        })).with_metadata(serde_json::json!({ "dry_run": true, "cross_device": cross_device })));
     }
 
-    fs::rename(&from, &to).map_err(|e| {
-        if e.kind() == std::io::ErrorKind::PermissionDenied {
-            EzError::PermissionDenied(format!("Cannot move '{}': {}", from.display(), e))
-        } else {
-            EzError::General(format!("Cannot move '{}': {}", from.display(), e))
+    match fs::rename(&from, &to) {
+        Ok(()) => {}
+        Err(e) if e.raw_os_error() == Some(libc::EXDEV) => {
+            let stats = move_across_devices(&from, &to, ctx)?;
+
+            if !ctx.json {
+                println!("{} Moved '{}' to '{}' ({} file(s), {} copied and verified across filesystems)",
+                    "✓".green(),
+                    from.display(),
+                    to.display(),
+                    stats.files,
+                    crate::utils::format_size(stats.bytes));
+            }
+
+            return Ok(CommandOutput::new("move", serde_json::json!({
+                "from": from.display().to_string(),
+                "to": to.display().to_string(),
+            })).with_metadata(serde_json::json!({
+                "cross_device": true,
+                "verified": true,
+                "files": stats.files,
+                "bytes": stats.bytes,
+            })));
         }
-    })?;
+        Err(e) => {
+            return Err(if e.kind() == std::io::ErrorKind::PermissionDenied {
+                EzError::PermissionDenied(format!("Cannot move '{}': {}", from.display(), e))
+            } else {
+                EzError::General(format!("Cannot move '{}': {}", from.display(), e))
+            });
+        }
+    }
 
     if !ctx.json {
         println!("{} Moved '{}' to '{}'", "✓".green(), from.display(), to.display());
@@ -46,3 +80,116 @@ pub fn execute(from: PathBuf, to: PathBuf, if_not_exists: bool, ctx: &CommandCon
         "to": to.display().to_string(),
     })))
 }
+
+/// Copy into a hidden staging path next to the destination, verify checksums,
+/// rename it into place (same filesystem, so atomic) and only then remove the
+/// source. Any failure before the final rename leaves the source untouched.
+fn move_across_devices(from: &Path, to: &Path, ctx: &CommandContext) -> Result<CopyStats, EzError> {
+    let name = to.file_name().ok_or_else(|| {
+        EzError::InvalidArgs(format!("Invalid destination '{}'", to.display()))
+    })?;
+    let staging = to.with_file_name(format!(
+        ".{}.ez-move-{}",
+        name.to_string_lossy(),
+        uuid::Uuid::new_v4().simple()
+    ));
+
+    let pb = if ctx.json { None } else { Some(crate::utils::byte_progress_bar(total_bytes(from))) };
+    let copied = copy::copy_verified(from, &staging, pb.as_ref());
+    if let Some(pb) = &pb {
+        pb.finish_and_clear();
+    }
+
+    let stats = match copied {
+        Ok(stats) => stats,
+        Err(e) => {
+            remove_path(&staging);
+            return Err(e);
+        }
+    };
+
+    if let Err(e) = fs::rename(&staging, to) {
+        remove_path(&staging);
+        return Err(EzError::General(format!("Cannot move '{}': {}", from.display(), e)));
+    }
+
+    let removed = if from.is_dir() && !from.is_symlink() {
+        fs::remove_dir_all(from)
+    } else {
+        fs::remove_file(from)
+    };
+    removed.map_err(|e| EzError::General(format!(
+        "Copied '{}' to '{}' but could not remove the original: {}",
+        from.display(), to.display(), e
+    )))?;
+
+    Ok(stats)
+}
+
+fn is_cross_device(from: &Path, to: &Path) -> bool {
+    let parent = match to.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    match (fs::symlink_metadata(from), fs::metadata(parent)) {
+        (Ok(a), Ok(b)) => a.dev() != b.dev(),
+        _ => false,
+    }
+}
+
+fn total_bytes(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+fn remove_path(path: &Path) {
+    let _ = if path.is_dir() && !path.is_symlink() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> CommandContext {
+        CommandContext { json: true, yes: true, dry_run: false, is_tty: false, is_stdin_tty: false }
+    }
+
+    #[test]
+    fn cross_device_moves_copy_verify_then_remove() {
+        // Same filesystem here, but the copy-verify-rename path is the one a real EXDEV takes
+        let tmp = tempfile::tempdir().unwrap();
+        let from = tmp.path().join("photos");
+        fs::create_dir(&from).unwrap();
+        fs::write(from.join("a.jpg"), "aaa").unwrap();
+        fs::write(from.join("b.jpg"), "bbbb").unwrap();
+        let to = tmp.path().join("archive");
+
+        let Ok(stats) = move_across_devices(&from, &to, &ctx()) else { panic!("move failed") };
+        assert_eq!((stats.files, stats.bytes), (2, 7));
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(to.join("b.jpg")).unwrap(), "bbbb");
+        // Nothing left over from staging
+        assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn failed_copies_leave_the_source_alone() {
+        let tmp = tempfile::tempdir().unwrap();
+        let from = tmp.path().join("notes.txt");
+        fs::write(&from, "keep").unwrap();
+        let to = tmp.path().join("missing-folder").join("notes.txt");
+
+        assert!(move_across_devices(&from, &to, &ctx()).is_err());
+        assert_eq!(fs::read_to_string(&from).unwrap(), "keep");
+        assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 1);
+    }
+}
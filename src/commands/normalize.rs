@@ -0,0 +1,305 @@
+use std::fs;
+use std::path::PathBuf;
+use colored::*;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use crate::context::CommandContext;
+use crate::output::{CommandOutput, EzError, EXIT_FOUND};
+use crate::LineEnding;
+
+/// What a file looks like on disk before normalizing.
+struct Detected {
+    encoding: &'static str,
+    bom: bool,
+    text: String,
+}
+
+/// Why a file was left alone.
+enum Skip {
+    Binary,
+    /// Valid UTF-8 text mixed with bytes that aren't; guessing would garble one or the other
+    MixedEncoding,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    paths: Vec<PathBuf>,
+    check: bool,
+    eol: LineEnding,
+    keep_bom: bool,
+    keep_whitespace: bool,
+    from: Option<String>,
+    no_ignore: bool,
+    backup: Option<String>,
+    ctx: &CommandContext,
+) -> Result<CommandOutput, EzError> {
+    if paths.is_empty() {
+        return Err(EzError::InvalidArgs("No files given. Pass files, folders or globs, or pipe in paths".to_string()));
+    }
+    let from = match &from {
+        Some(label) => Some(Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| EzError::InvalidArgs(format!("Unknown encoding '{}'. Try latin1, windows-1252, shift_jis, gbk", label)))?),
+        None => None,
+    };
+
+    let files = crate::utils::collect_files(&crate::utils::expand_globs(paths), !no_ignore);
+    let mut results = Vec::new();
+    let mut skipped_binary = Vec::new();
+    let mut skipped_mixed = Vec::new();
+    let mut scanned = 0;
+
+    for file in &files {
+        let bytes = fs::read(file).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                EzError::NotFound(format!("Cannot read '{}': {}", file.display(), e))
+            } else if e.kind() == std::io::ErrorKind::PermissionDenied {
+                EzError::PermissionDenied(format!("Cannot read '{}': {}", file.display(), e))
+            } else {
+                EzError::General(format!("Cannot read '{}': {}", file.display(), e))
+            }
+        })?;
+
+        let detected = match detect(&bytes, from) {
+            Ok(detected) => detected,
+            Err(Skip::Binary) => {
+                skipped_binary.push(file.display().to_string());
+                continue;
+            }
+            Err(Skip::MixedEncoding) => {
+                skipped_mixed.push(file.display().to_string());
+                continue;
+            }
+        };
+        scanned += 1;
+
+        let mut issues = Vec::new();
+        if detected.encoding != "UTF-8" {
+            issues.push(format!("encoding: {}", detected.encoding));
+        }
+        if detected.bom && !(keep_bom && detected.encoding == "UTF-8") {
+            issues.push("byte order mark".to_string());
+        }
+        let crlf = detected.text.matches("\r\n").count();
+        let lone_cr = detected.text.matches('\r').count() - crlf;
+        let lf = detected.text.matches('\n').count() - crlf;
+        match eol {
+            LineEnding::Lf if crlf > 0 => issues.push(format!("CRLF line endings ({} line(s))", crlf)),
+            LineEnding::Crlf if lf > 0 => issues.push(format!("LF line endings ({} line(s))", lf)),
+            _ => {}
+        }
+        if lone_cr > 0 && !matches!(eol, LineEnding::Keep) {
+            issues.push(format!("old Mac (CR) line endings ({} line(s))", lone_cr));
+        }
+        let trailing = detected.text.lines().filter(|l| l.ends_with([' ', '\t'])).count();
+        if trailing > 0 && !keep_whitespace {
+            issues.push(format!("trailing whitespace ({} line(s))", trailing));
+        }
+
+        let mut text = match eol {
+            LineEnding::Lf => detected.text.replace("\r\n", "\n").replace('\r', "\n"),
+            LineEnding::Crlf => detected.text.replace("\r\n", "\n").replace('\r', "\n").replace('\n', "\r\n"),
+            LineEnding::Keep => detected.text.clone(),
+        };
+        if !keep_whitespace {
+            text = strip_trailing_whitespace(&text);
+        }
+        let mut output = Vec::with_capacity(text.len() + 3);
+        if keep_bom && detected.bom {
+            output.extend_from_slice(b"\xEF\xBB\xBF");
+        }
+        output.extend_from_slice(text.as_bytes());
+
+        if output == bytes {
+            continue;
+        }
+        // e.g. a UTF-16 file with nothing else wrong
+        if issues.is_empty() {
+            issues.push("re-encoded".to_string());
+        }
+
+        let mut entry = serde_json::json!({
+            "file": file.display().to_string(),
+            "encoding": detected.encoding,
+            "issues": issues,
+        });
+
+        if !check && !ctx.dry_run {
+            let backup_path = crate::utils::write_atomic(file, &output, backup.as_deref()).map_err(|e| {
+                if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    EzError::PermissionDenied(format!("Cannot write to '{}': {}", file.display(), e))
+                } else {
+                    EzError::General(format!("Cannot write to '{}': {}", file.display(), e))
+                }
+            })?;
+            if let Some(b) = backup_path {
+                entry["backup"] = serde_json::json!(b.display().to_string());
+            }
+        }
+
+        if !ctx.json {
+            let mark = if check || ctx.dry_run { "~".dimmed() } else { "✓".green() };
+            println!("{} {}", mark, file.display().to_string().cyan());
+            for issue in entry["issues"].as_array().unwrap() {
+                println!("    {}", issue.as_str().unwrap_or_default().yellow());
+            }
+        }
+        results.push(entry);
+    }
+
+    if !ctx.json {
+        if results.is_empty() {
+            println!("{} All {} file(s) are already normalized", "✓".green(), scanned);
+        } else if check {
+            println!("\n{} {} of {} file(s) need normalizing. Run without --check to fix them",
+                "ℹ️".yellow(), results.len(), scanned);
+        } else if ctx.dry_run {
+            println!("\n{} Would normalize {} of {} file(s)", "~".dimmed(), results.len(), scanned);
+        } else {
+            println!("\n{} Normalized {} of {} file(s)", "✓".green(), results.len(), scanned);
+        }
+        if !skipped_binary.is_empty() {
+            println!("{} Skipped {} binary file(s)", "~".dimmed(), skipped_binary.len());
+        }
+        if !skipped_mixed.is_empty() {
+            println!("{} Skipped {} file(s) mixing UTF-8 with another encoding. Pass --from to say how to read them:",
+                "ℹ️".yellow(), skipped_mixed.len());
+            for file in &skipped_mixed {
+                println!("    {}", file.cyan());
+            }
+        }
+    }
+
+    let mut metadata = serde_json::json!({
+        "files_scanned": scanned,
+        "files_changed": if check { 0 } else { results.len() },
+        "skipped_binary": skipped_binary,
+        "skipped_mixed_encoding": skipped_mixed,
+        "eol": match eol { LineEnding::Lf => "lf", LineEnding::Crlf => "crlf", LineEnding::Keep => "keep" },
+    });
+    if check {
+        metadata["check"] = serde_json::json!(true);
+    }
+    if ctx.dry_run {
+        metadata["dry_run"] = serde_json::json!(true);
+    }
+
+    let needs_changes = check && !results.is_empty();
+    let output = CommandOutput::new("normalize", serde_json::json!({ "files": results })).with_metadata(metadata);
+    Ok(if needs_changes { output.with_exit_code(EXIT_FOUND) } else { output })
+}
+
+/// Work out the encoding and decode. UTF-16 is only recognized by its BOM.
+/// Text that isn't valid UTF-8 is decoded with `from`, or as windows-1252 when
+/// it has no UTF-8 at all (a mix is refused rather than guessed).
+fn detect(bytes: &[u8], from: Option<&'static Encoding>) -> Result<Detected, Skip> {
+    for (bom, encoding) in [(&b"\xFF\xFE"[..], UTF_16LE), (&b"\xFE\xFF"[..], UTF_16BE)] {
+        if let Some(rest) = bytes.strip_prefix(bom) {
+            let (text, _) = encoding.decode_without_bom_handling(rest);
+            return Ok(Detected { encoding: encoding.name(), bom: true, text: text.into_owned() });
+        }
+    }
+    let (bom, bytes) = match bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        Some(rest) => (true, rest),
+        None => (false, bytes),
+    };
+    if crate::utils::is_binary(bytes) {
+        return Err(Skip::Binary);
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Ok(Detected { encoding: "UTF-8", bom, text: text.to_string() });
+    }
+    let encoding = match from {
+        Some(encoding) => encoding,
+        None if has_utf8_multibyte(bytes) => return Err(Skip::MixedEncoding),
+        None => WINDOWS_1252,
+    };
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    Ok(Detected { encoding: encoding.name(), bom, text: text.into_owned() })
+}
+
+/// Whether the valid stretches of `bytes` hold any non-ASCII UTF-8 character.
+fn has_utf8_multibyte(mut bytes: &[u8]) -> bool {
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => return !text.is_ascii(),
+            Err(e) => {
+                if !bytes[..e.valid_up_to()].is_ascii() {
+                    return true;
+                }
+                match e.error_len() {
+                    Some(len) => bytes = &bytes[e.valid_up_to() + len..],
+                    // Cut off mid-character at the end
+                    None => return false,
+                }
+            }
+        }
+    }
+}
+
+/// Drop spaces and tabs before each line ending, keeping the endings themselves.
+fn strip_trailing_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\n', '\r']);
+        out.push_str(body.trim_end_matches([' ', '\t']));
+        out.push_str(&line[body.len()..]);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::SHIFT_JIS;
+
+    fn decode(bytes: &[u8], from: Option<&'static Encoding>) -> Detected {
+        let Ok(detected) = detect(bytes, from) else { panic!("{:?} was skipped", bytes) };
+        detected
+    }
+
+    #[test]
+    fn utf8_with_and_without_bom() {
+        let plain = decode("café\n".as_bytes(), None);
+        assert_eq!((plain.encoding, plain.bom, plain.text.as_str()), ("UTF-8", false, "café\n"));
+        let bom = decode(b"\xEF\xBB\xBFcaf\xC3\xA9\n", None);
+        assert_eq!((bom.encoding, bom.bom, bom.text.as_str()), ("UTF-8", true, "café\n"));
+    }
+
+    #[test]
+    fn latin1_falls_back_to_windows_1252() {
+        let detected = decode(b"caf\xE9 \x80\n", None);
+        assert_eq!((detected.encoding, detected.text.as_str()), ("windows-1252", "café €\n"));
+    }
+
+    #[test]
+    fn bom_is_stripped_before_falling_back() {
+        let detected = decode(b"\xEF\xBB\xBFcaf\xE9\n", None);
+        assert_eq!((detected.encoding, detected.bom, detected.text.as_str()), ("windows-1252", true, "café\n"));
+    }
+
+    #[test]
+    fn utf16_is_recognized_by_its_bom() {
+        let detected = decode(b"\xFF\xFEh\x00i\x00", None);
+        assert_eq!((detected.encoding, detected.bom, detected.text.as_str()), ("UTF-16LE", true, "hi"));
+    }
+
+    #[test]
+    fn mixed_encodings_are_not_guessed() {
+        // "naïve" in UTF-8, then a stray Latin-1 "é"
+        let mixed = b"na\xC3\xAFve caf\xE9\n";
+        assert!(matches!(detect(mixed, None), Err(Skip::MixedEncoding)));
+        assert!(has_utf8_multibyte(mixed));
+        assert!(!has_utf8_multibyte(b"caf\xE9\n"));
+        // --from says how to read them
+        assert_eq!(decode(b"\x93\xFA\x96\x7B", Some(SHIFT_JIS)).text, "日本");
+    }
+
+    #[test]
+    fn binary_files_are_skipped() {
+        assert!(matches!(detect(b"\x7FELF\x00\x00\x01", None), Err(Skip::Binary)));
+    }
+
+    #[test]
+    fn trailing_whitespace_keeps_line_endings() {
+        assert_eq!(strip_trailing_whitespace("a  \r\nb\t\nc "), "a\r\nb\nc");
+    }
+}
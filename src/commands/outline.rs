@@ -0,0 +1,695 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use colored::*;
+use crate::context::CommandContext;
+use crate::output::{CommandOutput, EzError};
+use crate::walk::WalkOptions;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    Markdown,
+}
+
+impl Language {
+    fn detect(path: &Path) -> Option<Language> {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        let language = match ext.as_str() {
+            "rs" => Language::Rust,
+            "py" | "pyi" => Language::Python,
+            "js" | "jsx" | "mjs" | "cjs" => Language::JavaScript,
+            "ts" | "tsx" | "mts" | "cts" => Language::TypeScript,
+            "go" => Language::Go,
+            "md" | "markdown" => Language::Markdown,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::Go => "go",
+            Language::Markdown => "markdown",
+        }
+    }
+}
+
+/// A function, type, heading, ... and the lines it spans.
+struct Symbol {
+    name: String,
+    kind: &'static str,
+    line: usize,
+    end_line: usize,
+    /// How many symbols it's nested in (for headings, the level minus one)
+    depth: usize,
+    /// The declaring line, up to its opening brace
+    signature: Option<String>,
+}
+
+pub fn execute(path: PathBuf, threads: usize, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    let meta = fs::metadata(&path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            EzError::NotFound(format!("Cannot access '{}': {}", path.display(), e))
+        } else {
+            EzError::General(format!("Cannot access '{}': {}", path.display(), e))
+        }
+    })?;
+
+    let files = if meta.is_dir() {
+        // Skip what git ignores (build output, vendored code), like an editor's symbol search would
+        let options = WalkOptions { hidden: false, respect_ignore: true, skip_git: true, threads, ..Default::default() };
+        crate::walk::walk(&path, &options, |entry| {
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                return None;
+            }
+            let language = Language::detect(entry.path())?;
+            let symbols = outline_file(entry.path(), language).ok()?;
+            (!symbols.is_empty()).then_some((language, symbols))
+        })
+    } else {
+        let language = Language::detect(&path).ok_or_else(|| EzError::InvalidArgs(format!(
+            "Don't know how to outline '{}' (supported: .rs, .py, .js, .ts, .go, .md)", path.display()
+        )))?;
+        let symbols = outline_file(&path, language)
+            .map_err(|e| EzError::General(format!("Cannot read '{}': {}", path.display(), e)))?;
+        vec![(path.clone(), (language, symbols))]
+    };
+
+    let total: usize = files.iter().map(|(_, (_, symbols))| symbols.len()).sum();
+    if !ctx.json {
+        for (file, (language, symbols)) in &files {
+            print_outline(file, *language, symbols);
+        }
+        if meta.is_dir() {
+            println!("\n{} {} symbol(s) in {} file(s)", "✓".green(), total, files.len());
+        }
+    }
+
+    let data: Vec<_> = files.iter().map(|(file, (language, symbols))| serde_json::json!({
+        "file": file.display().to_string(),
+        "language": language.name(),
+        "symbols": symbols.iter().map(|s| {
+            let mut symbol = serde_json::json!({
+                "name": s.name,
+                "kind": s.kind,
+                "line": s.line,
+                "end_line": s.end_line,
+                "depth": s.depth,
+            });
+            if let Some(signature) = &s.signature {
+                symbol["signature"] = serde_json::json!(signature);
+            }
+            symbol
+        }).collect::<Vec<_>>(),
+    })).collect();
+
+    Ok(CommandOutput::new("outline", serde_json::json!(data))
+        .with_metadata(serde_json::json!({
+            "files": files.len(),
+            "symbols": total,
+        })))
+}
+
+fn print_outline(file: &Path, language: Language, symbols: &[Symbol]) {
+    println!("\n{} {} {}", "📄".cyan(), file.display().to_string().bold(), format!("({})", language.name()).dimmed());
+    if symbols.is_empty() {
+        println!("  {}", "(nothing found)".dimmed());
+    }
+    for symbol in symbols {
+        let indent = "  ".repeat(symbol.depth);
+        let name = if symbol.kind == "heading" {
+            format!("{} {}", "#".repeat(symbol.depth + 1), symbol.name).bold()
+        } else {
+            symbol.name.bold()
+        };
+        let kind = match symbol.kind {
+            "heading" => {
+                println!("{:>6}  {}{}", symbol.line.to_string().dimmed(), indent, name);
+                continue;
+            }
+            "function" | "method" | "macro" => symbol.kind.green(),
+            "impl" | "module" => symbol.kind.dimmed(),
+            _ => symbol.kind.yellow(),
+        };
+        println!("{:>6}  {}{:<10} {}", symbol.line.to_string().dimmed(), indent, kind, name);
+    }
+}
+
+fn outline_file(path: &Path, language: Language) -> std::io::Result<Vec<Symbol>> {
+    let bytes = fs::read(path)?;
+    if crate::utils::is_binary(&bytes) {
+        return Ok(Vec::new());
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    Ok(match language {
+        Language::Python => outline_python(&lines),
+        Language::Markdown => outline_markdown(&lines),
+        _ => outline_braces(&lines, language),
+    })
+}
+
+/// Brace languages: each line is matched against the language's declarations,
+/// and `{`/`}` (outside strings and comments) say where bodies start and end.
+/// Nothing inside a function body is listed.
+fn outline_braces(lines: &[&str], language: Language) -> Vec<Symbol> {
+    let mut symbols: Vec<Symbol> = Vec::new();
+    // One per open brace, with the symbol whose body it is
+    let mut frames: Vec<Option<usize>> = Vec::new();
+    let mut cleaner = Cleaner::default();
+    // A declaration whose body hasn't opened yet (Rust allows `where` lines in between)
+    let mut pending: Option<usize> = None;
+    let mut parens = 0usize;
+
+    for (i, raw) in lines.iter().enumerate() {
+        let number = i + 1;
+        let starts_in_string = cleaner.in_string();
+        let line = cleaner.clean(raw, language);
+
+        // Where a declaration may start: the line itself, then after each `{` that
+        // opens a type or impl, so `impl X { fn m() {} }` lists `m` too
+        let mut start = if starts_in_string { None } else { Some(0) };
+        let mut pos = 0;
+        while pos < line.len() {
+            if let Some(from) = start.take() {
+                let containers: Vec<&'static str> = frames.iter().flatten().map(|s| symbols[*s].kind).collect();
+                let in_function = containers.iter().any(|k| matches!(*k, "function" | "method"));
+                if !in_function && pending.is_none() {
+                    let parent = containers.last().copied();
+                    if let Some((kind, name)) = declaration(line[from..].trim_start(), language, parent) {
+                        let signature = if from == 0 { raw.trim() } else { line[from..].trim() };
+                        let signature = signature.split_once(" {").map_or(signature, |(s, _)| s).trim_end_matches('{').trim_end();
+                        symbols.push(Symbol {
+                            name,
+                            kind,
+                            line: number,
+                            end_line: number,
+                            depth: containers.len(),
+                            signature: Some(signature.chars().take(160).collect()),
+                        });
+                        pending = Some(symbols.len() - 1);
+                    }
+                }
+            }
+
+            let base = pos;
+            for (offset, c) in line[base..].char_indices() {
+                let at = base + offset;
+                match c {
+                    '(' | '[' => parens += 1,
+                    ')' | ']' => parens = parens.saturating_sub(1),
+                    '{' => {
+                        let opened = pending.take();
+                        frames.push(opened);
+                        if opened.is_some_and(|s| !matches!(symbols[s].kind, "function" | "method")) {
+                            start = Some(at + 1);
+                        }
+                    }
+                    '}' => {
+                        if let Some(Some(s)) = frames.pop() {
+                            symbols[s].end_line = number;
+                        }
+                    }
+                    // A declaration without a body: `struct Unit;`, a trait method, ...
+                    ';' if parens == 0 => pending = None,
+                    _ => {}
+                }
+                pos = at + c.len_utf8();
+                if start.is_some() {
+                    break;
+                }
+            }
+        }
+        // Go and JS put the opening brace on the declaring line
+        if language != Language::Rust && parens == 0 {
+            pending = None;
+        }
+    }
+    symbols
+}
+
+/// Kind and name of whatever `line` (trimmed, strings emptied) declares.
+fn declaration(line: &str, language: Language, parent: Option<&str>) -> Option<(&'static str, String)> {
+    match language {
+        Language::Rust => {
+            let mut rest = line;
+            loop {
+                if let Some(after) = rest.strip_prefix("pub(") {
+                    rest = after.split_once(')')?.1.trim_start();
+                } else if let Some(after) = strip_word(rest, &["pub", "async", "unsafe", "default", "extern", "\"\""]) {
+                    rest = after;
+                } else if rest.starts_with("const fn") || rest.starts_with("const unsafe") || rest.starts_with("const async") {
+                    rest = strip_word(rest, &["const"])?;
+                } else {
+                    break;
+                }
+            }
+            if let Some(after) = rest.strip_prefix("impl").filter(|a| a.starts_with(['<', ' '])) {
+                // `impl<T> Display for Wrapper<T> where ...` reads as `Display for Wrapper<T>`
+                let header = if after.starts_with('<') { skip_generics(after) } else { after.trim_start() };
+                // One-line impls carry their body along: `impl X { fn m() {} }`
+                let header = header.split('{').next().unwrap_or(header);
+                let header = header.split(" where").next().unwrap_or(header).trim();
+                return (!header.is_empty()).then(|| ("impl", header.to_string()));
+            }
+            let (word, after) = split_word(rest);
+            let kind = match word {
+                "fn" if matches!(parent, Some("impl" | "trait")) => "method",
+                "fn" => "function",
+                "struct" => "struct",
+                "enum" => "enum",
+                "trait" => "trait",
+                "union" => "union",
+                "type" => "type",
+                "mod" => "module",
+                "macro_rules!" => "macro",
+                _ => return None,
+            };
+            ident(after, false).map(|name| (kind, name))
+        }
+        Language::Go => {
+            let (word, after) = split_word(line);
+            match word {
+                "func" if after.starts_with('(') => {
+                    // Methods: func (s *Server) Start(...)
+                    let (receiver, rest) = after[1..].split_once(')')?;
+                    let receiver = receiver.split_whitespace().last()?.trim_start_matches('*');
+                    ident(rest.trim_start(), false).map(|name| ("method", format!("{}.{}", receiver, name)))
+                }
+                "func" => ident(after, false).map(|name| ("function", name)),
+                "type" => {
+                    let name = ident(after, false)?;
+                    let definition = after[name.len()..].trim_start();
+                    let definition = if definition.starts_with('[') { skip_brackets(definition) } else { definition };
+                    let kind = match ident(definition, false).as_deref().unwrap_or_default() {
+                        "struct" => "struct",
+                        "interface" => "interface",
+                        _ => "type",
+                    };
+                    Some((kind, name))
+                }
+                _ => None,
+            }
+        }
+        _ => {
+            let js = strip_words(line, &["export", "default", "declare", "abstract"]);
+            let (word, after) = split_word(js);
+            match word {
+                "function" | "function*" | "async" if strip_words(js, &["async"]).starts_with("function") => {
+                    let after = strip_words(js, &["async", "function", "function*"]).trim_start_matches('*').trim_start();
+                    ident(after, true).map(|name| ("function", name))
+                }
+                "class" => ident(after, true).map(|name| ("class", name)),
+                "interface" => ident(after, true).map(|name| ("interface", name)),
+                "enum" => ident(after, true).map(|name| ("enum", name)),
+                "namespace" | "module" => ident(after, true).map(|name| ("module", name)),
+                "type" => {
+                    let name = ident(after, true)?;
+                    after[name.len()..].trim_start().starts_with(['=', '<']).then_some(("type", name))
+                }
+                // const handler = async (req) => { ... }
+                "const" | "let" | "var" => {
+                    let name = ident(after, true)?;
+                    let value = after[name.len()..].trim_start();
+                    let value = if value.starts_with(':') { value.split_once('=').map_or("", |(_, v)| v) } else { value.strip_prefix('=')? };
+                    let value = value.trim_start();
+                    let is_function = value.starts_with("function") || value.starts_with("async") || value.contains("=>");
+                    is_function.then_some(("function", name))
+                }
+                _ if matches!(parent, Some("class")) => {
+                    let member = strip_words(js, &["static", "async", "get", "set", "public", "private", "protected", "readonly", "override"]);
+                    let member = member.trim_start_matches('*');
+                    let name = ident(member.trim_start_matches('#'), true)?;
+                    let after = member.trim_start_matches('#')[name.len()..].trim_start();
+                    let after = if after.starts_with('<') { skip_generics(after) } else { after };
+                    let keyword = matches!(name.as_str(), "if" | "for" | "while" | "switch" | "catch" | "return" | "super");
+                    (!keyword && after.starts_with('(')).then_some(("method", name))
+                }
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Python has no braces: a `def` or `class` owns every deeper-indented line after it.
+fn outline_python(lines: &[&str]) -> Vec<Symbol> {
+    let mut symbols: Vec<Symbol> = Vec::new();
+    // (indent, symbol index)
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut last_code = 0;
+    let mut in_docstring: Option<&str> = None;
+
+    for (i, raw) in lines.iter().enumerate() {
+        let number = i + 1;
+        let trimmed = raw.trim_start();
+        if let Some(quote) = in_docstring {
+            if trimmed.contains(quote) {
+                in_docstring = None;
+            }
+            last_code = number;
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = raw.len() - trimmed.len();
+        while open.last().is_some_and(|&(level, _)| level >= indent) {
+            let (_, s) = open.pop().unwrap_or_default();
+            symbols[s].end_line = last_code;
+        }
+        last_code = number;
+        for quote in ["\"\"\"", "'''"] {
+            if trimmed.matches(quote).count() % 2 == 1 {
+                in_docstring = Some(quote);
+            }
+        }
+
+        let in_function = open.iter().any(|&(_, s)| symbols[s].kind != "class");
+        let (word, after) = split_word(strip_words(trimmed, &["async"]));
+        let kind = match word {
+            "class" => "class",
+            "def" if open.last().is_some_and(|&(_, s)| symbols[s].kind == "class") => "method",
+            "def" => "function",
+            _ => continue,
+        };
+        if in_function {
+            continue;
+        }
+        if let Some(name) = ident(after, false) {
+            symbols.push(Symbol {
+                name,
+                kind,
+                line: number,
+                end_line: number,
+                depth: open.len(),
+                signature: Some(trimmed.trim_end().chars().take(160).collect()),
+            });
+            open.push((indent, symbols.len() - 1));
+        }
+    }
+    for (_, s) in open {
+        symbols[s].end_line = last_code;
+    }
+    symbols
+}
+
+/// `#` headings and underlined (`===`/`---`) ones, skipping fenced code blocks.
+/// A section runs until the next heading of the same or a higher level.
+fn outline_markdown(lines: &[&str]) -> Vec<Symbol> {
+    let mut headings: Vec<(usize, usize, String)> = Vec::new();
+    let mut fence: Option<&str> = None;
+    // YAML front matter's closing --- isn't an underline
+    let front_matter = match lines.first() {
+        Some(first) if first.trim() == "---" => lines.iter().skip(1).position(|l| l.trim() == "---").map_or(0, |end| end + 2),
+        _ => 0,
+    };
+    for (i, raw) in lines.iter().enumerate().skip(front_matter) {
+        let trimmed = raw.trim();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+        // Four spaces of indent make a code block, and #hashtag isn't a heading
+        let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+        let indent = raw.len() - raw.trim_start().len();
+        let after = &trimmed[hashes..];
+        if (1..=6).contains(&hashes) && indent < 4 && (after.is_empty() || after.starts_with([' ', '\t'])) {
+            let text = after.trim().trim_end_matches('#').trim();
+            headings.push((i + 1, hashes, text.to_string()));
+            continue;
+        }
+        // Setext: a paragraph line underlined with === or ---
+        let underline = !trimmed.is_empty() && (trimmed.chars().all(|c| c == '=') || trimmed.chars().all(|c| c == '-'));
+        if underline && i > 0 {
+            let previous = lines[i - 1].trim();
+            let is_text = !previous.is_empty() && !previous.starts_with(['#', '-', '*', '>', '|']);
+            if is_text && headings.last().map_or(true, |h| h.0 != i) {
+                headings.push((i, if trimmed.starts_with('=') { 1 } else { 2 }, previous.to_string()));
+            }
+        }
+    }
+
+    let last_line = lines.iter().rposition(|l| !l.trim().is_empty()).map_or(0, |i| i + 1);
+    (0..headings.len()).map(|h| {
+        let (line, level, ref text) = headings[h];
+        let end = headings[h + 1..].iter().find(|next| next.1 <= level).map_or(last_line, |next| next.0 - 1);
+        Symbol { name: text.clone(), kind: "heading", line, end_line: end.max(line), depth: level - 1, signature: None }
+    }).collect()
+}
+
+/// Blanks out comments and string contents (keeping the quotes) so braces and
+/// keywords inside them aren't mistaken for code. Strings and block comments
+/// can span lines, so this carries state from one line to the next.
+#[derive(Default)]
+struct Cleaner {
+    block_comment: bool,
+    /// Closing delimiter of the string we're inside
+    string: Option<String>,
+}
+
+impl Cleaner {
+    fn in_string(&self) -> bool {
+        self.string.is_some() || self.block_comment
+    }
+
+    fn clean(&mut self, line: &str, language: Language) -> String {
+        let chars: Vec<char> = line.chars().collect();
+        let mut out = String::with_capacity(line.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            if self.block_comment {
+                if c == '*' && next == Some('/') {
+                    self.block_comment = false;
+                    i += 1;
+                }
+                i += 1;
+                continue;
+            }
+            if let Some(close) = &self.string {
+                // Go's `raw strings` and Rust's r"raw strings" have no escapes
+                let escapes = matches!(close.as_str(), "\"" | "'") || (close == "`" && language != Language::Go);
+                if c == '\\' && escapes {
+                    i += 2;
+                    continue;
+                }
+                let closing: Vec<char> = close.chars().collect();
+                if chars[i..].starts_with(&closing) {
+                    out.push_str(close);
+                    self.string = None;
+                    i += closing.len();
+                } else {
+                    i += 1;
+                }
+                continue;
+            }
+            match c {
+                '/' if next == Some('/') => break,
+                '/' if next == Some('*') => {
+                    self.block_comment = true;
+                    i += 2;
+                    continue;
+                }
+                '"' => self.string = Some("\"".to_string()),
+                '`' if language != Language::Rust => self.string = Some("`".to_string()),
+                '\'' if language == Language::Rust => {
+                    // A char literal ('{', '\n'), not a lifetime ('a)
+                    if next == Some('\\') {
+                        if let Some(end) = chars.get(i + 3..).and_then(|rest| rest.iter().position(|&c| c == '\'')) {
+                            out.push_str("''");
+                            i += end + 4;
+                            continue;
+                        }
+                    } else if chars.get(i + 2) == Some(&'\'') {
+                        out.push_str("''");
+                        i += 3;
+                        continue;
+                    }
+                }
+                '\'' => self.string = Some("'".to_string()),
+                // Raw strings: r"..." and r#"..."#
+                'r' if language == Language::Rust && !prev_is_ident(&chars, i) => {
+                    let hashes = chars[i + 1..].iter().take_while(|&&c| c == '#').count();
+                    if chars.get(i + 1 + hashes) == Some(&'"') {
+                        out.push('"');
+                        self.string = Some(format!("\"{}", "#".repeat(hashes)));
+                        i += hashes + 2;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            out.push(c);
+            i += 1;
+        }
+        // Only template literals, Go raw strings and Rust strings carry on to the next line
+        if matches!(self.string.as_deref(), Some("'")) || (language != Language::Rust && self.string.as_deref() == Some("\"")) {
+            self.string = None;
+        }
+        out
+    }
+}
+
+fn prev_is_ident(chars: &[char], i: usize) -> bool {
+    i > 0 && (chars[i - 1].is_alphanumeric() || chars[i - 1] == '_')
+}
+
+/// The first whitespace-separated word and what follows it.
+fn split_word(s: &str) -> (&str, &str) {
+    match s.find(char::is_whitespace) {
+        Some(end) => (&s[..end], s[end..].trim_start()),
+        None => (s, ""),
+    }
+}
+
+/// `s` without its first word, if that word is one of `words`.
+fn strip_word<'a>(s: &'a str, words: &[&str]) -> Option<&'a str> {
+    let (word, rest) = split_word(s);
+    words.contains(&word).then_some(rest)
+}
+
+fn strip_words<'a>(mut s: &'a str, words: &[&str]) -> &'a str {
+    while let Some(rest) = strip_word(s, words) {
+        s = rest;
+    }
+    s
+}
+
+/// The identifier `s` starts with (JS identifiers may contain `$`).
+fn ident(s: &str, dollar: bool) -> Option<String> {
+    let name: String = s.chars().take_while(|&c| c.is_alphanumeric() || c == '_' || (dollar && c == '$')).collect();
+    (!name.is_empty() && !name.starts_with(|c: char| c.is_ascii_digit())).then_some(name)
+}
+
+/// What follows a leading `<...>`, allowing nesting.
+fn skip_generics(s: &str) -> &str {
+    skip_balanced(s, '<', '>')
+}
+
+fn skip_brackets(s: &str) -> &str {
+    skip_balanced(s, '[', ']')
+}
+
+/// What follows the bracketed part `s` starts with. The `>` of `->` and `=>`
+/// (`impl<F: Fn() -> T>`, `<T extends () => void>`) doesn't close anything.
+fn skip_balanced(s: &str, open: char, close: char) -> &str {
+    let mut depth = 0;
+    let mut prev = ' ';
+    for (i, c) in s.char_indices() {
+        let arrow = c == '>' && matches!(prev, '-' | '=');
+        prev = c;
+        if c == open {
+            depth += 1;
+        } else if c == close && !arrow {
+            depth -= 1;
+            if depth == 0 {
+                return s[i + 1..].trim_start();
+            }
+        }
+    }
+    ""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `kind name @ line-end_line`, indented by depth.
+    fn outline(language: Language, source: &str) -> Vec<String> {
+        let lines: Vec<&str> = source.lines().collect();
+        let symbols = match language {
+            Language::Python => outline_python(&lines),
+            Language::Markdown => outline_markdown(&lines),
+            _ => outline_braces(&lines, language),
+        };
+        symbols.iter()
+            .map(|s| format!("{}{} {} @ {}-{}", "  ".repeat(s.depth), s.kind, s.name, s.line, s.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn rust_items_and_methods() {
+        let source = "\
+pub struct Point<T> { x: T }
+
+impl<T: Copy> Point<T>
+where
+    T: Default,
+{
+    pub fn new(x: T) -> Self {
+        let inner = || {};
+        Point { x }
+    }
+}
+
+pub(crate) async fn run() {}
+";
+        assert_eq!(outline(Language::Rust, source), [
+            "struct Point @ 1-1",
+            "impl Point<T> @ 3-11",
+            "  method new @ 7-10",
+            "function run @ 13-13",
+        ]);
+    }
+
+    #[test]
+    fn arrows_inside_generics_and_one_line_impls() {
+        let source = "impl<T: Fn() -> ()> X<T> { fn m(&self) {} }\nfn after() {}\n";
+        assert_eq!(outline(Language::Rust, source), [
+            "impl X<T> @ 1-1",
+            "  method m @ 1-1",
+            "function after @ 2-2",
+        ]);
+        assert_eq!(skip_generics("<F: Fn(u8) -> bool> Rest"), "Rest");
+        assert_eq!(skip_generics("<T extends () => void>(x: T)"), "(x: T)");
+    }
+
+    #[test]
+    fn strings_and_comments_hide_braces() {
+        let source = "fn a() {\n    let s = \"}\"; // }\n}\nfn b() {}\n";
+        assert_eq!(outline(Language::Rust, source), ["function a @ 1-3", "function b @ 4-4"]);
+    }
+
+    #[test]
+    fn go_types_and_methods() {
+        let source = "type List[T any] struct {\n\titems []T\n}\n\nfunc (l *List[T]) Len() int {\n\treturn len(l.items)\n}\n";
+        assert_eq!(outline(Language::Go, source), ["struct List @ 1-3", "method List[T].Len @ 5-7"]);
+    }
+
+    #[test]
+    fn js_classes_and_arrow_functions() {
+        let source = "export class Api {\n  async get(url) {\n    if (x) {}\n  }\n}\nconst handler = async (req) => {\n};\n";
+        assert_eq!(outline(Language::JavaScript, source), [
+            "class Api @ 1-5",
+            "  method get @ 2-4",
+            "function handler @ 6-7",
+        ]);
+    }
+
+    #[test]
+    fn python_indentation() {
+        let source = "class A:\n    def m(self):\n        pass\n\ndef f():\n    \"\"\"def not_this():\"\"\"\n";
+        assert_eq!(outline(Language::Python, source), ["class A @ 1-3", "  method m @ 2-3", "function f @ 5-6"]);
+    }
+
+    #[test]
+    fn markdown_headings_skip_code_blocks() {
+        let source = "# Title\n\n```\n# not a heading\n```\n\n## Section\ntext\n";
+        assert_eq!(outline(Language::Markdown, source), ["heading Title @ 1-8", "  heading Section @ 7-8"]);
+    }
+}
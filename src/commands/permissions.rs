@@ -1,23 +1,28 @@
 use std::fs;
-use std::path::PathBuf;
-use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use walkdir::WalkDir;
 use colored::*;
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
 
 pub fn execute(path: PathBuf, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
-    let metadata = fs::metadata(&path).map_err(|e| {
-        if e.kind() == std::io::ErrorKind::NotFound {
-            EzError::NotFound(format!("Cannot read metadata for '{}': {}", path.display(), e))
-        } else if e.kind() == std::io::ErrorKind::PermissionDenied {
-            EzError::PermissionDenied(format!("Cannot read metadata for '{}': {}", path.display(), e))
-        } else {
-            EzError::General(format!("Cannot read metadata for '{}': {}", path.display(), e))
-        }
-    })?;
+    let link_meta = fs::symlink_metadata(&path).map_err(|e| metadata_error(&path, e))?;
+    let link_target = if link_meta.file_type().is_symlink() {
+        fs::read_link(&path).ok()
+    } else {
+        None
+    };
+    // Report on what a symlink points at; the link's own bits are meaningless
+    let metadata = if link_target.is_some() {
+        fs::metadata(&path).map_err(|e| metadata_error(&path, e))?
+    } else {
+        link_meta
+    };
 
-    let permissions = metadata.permissions();
-    let mode = permissions.mode();
+    let mode = metadata.permissions().mode();
+    let bits = mode & 0o7777;
+    let is_dir = metadata.is_dir();
 
     let user_read = mode & 0o400 != 0;
     let user_write = mode & 0o200 != 0;
@@ -29,24 +34,30 @@ pub fn execute(path: PathBuf, ctx: &CommandContext) -> Result<CommandOutput, EzE
     let other_write = mode & 0o002 != 0;
     let other_exec = mode & 0o001 != 0;
 
-    let perm_string = format!(
-        "{}{}{}{}{}{}{}{}{}",
-        if user_read { "r" } else { "-" },
-        if user_write { "w" } else { "-" },
-        if user_exec { "x" } else { "-" },
-        if group_read { "r" } else { "-" },
-        if group_write { "w" } else { "-" },
-        if group_exec { "x" } else { "-" },
-        if other_read { "r" } else { "-" },
-        if other_write { "w" } else { "-" },
-        if other_exec { "x" } else { "-" },
-    );
-
-    let octal = format!("{:o}", mode & 0o777);
+    let perm_string = mode_string(bits);
+    let octal = format!("{:o}", bits);
+    let kind = file_kind(&metadata.file_type());
+
+    let owner_name = lookup_name("/etc/passwd", metadata.uid());
+    let group_name = lookup_name("/etc/group", metadata.gid());
+    let warnings = special_warnings(bits, is_dir);
+    let has_acl = has_acl(&path);
+    let access = check_access(&path, &metadata);
 
     if !ctx.json {
         println!("{} {}", "📋 File:".bold(), path.display().to_string().cyan());
+        if let Some(target) = &link_target {
+            println!("{} {}", "🔗 Links to:".bold(), target.display().to_string().cyan());
+        }
+        println!("{} {}", "📄 Type:".bold(), kind);
+        println!("{} {} {}", "👤 Owner:".bold(),
+            owner_name.clone().unwrap_or_else(|| "unknown".to_string()).yellow(),
+            format!("(uid {})", metadata.uid()).dimmed());
+        println!("{} {} {}", "👥 Group:".bold(),
+            group_name.clone().unwrap_or_else(|| "unknown".to_string()).yellow(),
+            format!("(gid {})", metadata.gid()).dimmed());
         println!("{} {} ({})", "🔐 Permissions:".bold(), perm_string.yellow(), octal.dimmed());
+        println!("   {}", explain_mode(bits).dimmed());
         println!();
         println!("{}", "Breakdown:".bold());
         println!("  {} {} {} {}",
@@ -67,14 +78,535 @@ pub fn execute(path: PathBuf, ctx: &CommandContext) -> Result<CommandOutput, EzE
             if other_write { "✓ write".green() } else { "✗ write".dimmed() },
             if other_exec { "✓ execute".green() } else { "✗ execute".dimmed() }
         );
+
+        if bits & 0o7000 != 0 {
+            println!();
+            println!("{}", "Special bits:".bold());
+            if bits & 0o4000 != 0 {
+                println!("  {} setuid: runs with the owner's privileges", "•".cyan());
+            }
+            if bits & 0o2000 != 0 {
+                if is_dir {
+                    println!("  {} setgid: new files inside inherit the folder's group", "•".cyan());
+                } else {
+                    println!("  {} setgid: runs with the group's privileges", "•".cyan());
+                }
+            }
+            if bits & 0o1000 != 0 {
+                println!("  {} sticky: only owners can delete or rename entries inside", "•".cyan());
+            }
+        }
+        if has_acl {
+            println!();
+            println!("{} Has ACL entries; actual access may differ (see getfacl)", "ℹ️".yellow());
+        }
+        if !warnings.is_empty() {
+            println!();
+            for warning in &warnings {
+                println!("{} {}", "⚠️".yellow(), warning.yellow());
+            }
+        }
+
+        println!();
+        let who = access.user.clone().unwrap_or_else(|| format!("uid {}", access.uid));
+        println!("{} {} {}", "You".bold(), format!("({}, {})", who, access.role).dimmed(), "can:".bold());
+        println!("  {} {} {}",
+            if access.read { "✓ read".green() } else { "✗ read".red() },
+            if access.write { "✓ write".green() } else { "✗ write".red() },
+            if access.execute { "✓ execute".green() } else { "✗ execute".red() });
+        if let Some(blocked) = &access.blocked_by {
+            println!("  {} Cannot reach it: no permission to enter '{}'", "✗".red(), blocked.display());
+        }
     }
 
     Ok(CommandOutput::new("permissions", serde_json::json!({
         "path": path.display().to_string(),
+        "type": kind,
+        "link_target": link_target.map(|t| t.display().to_string()),
         "octal": octal,
         "string": perm_string,
+        "explanation": explain_mode(bits),
         "user": { "read": user_read, "write": user_write, "execute": user_exec },
         "group": { "read": group_read, "write": group_write, "execute": group_exec },
         "other": { "read": other_read, "write": other_write, "execute": other_exec },
+        "special": {
+            "setuid": bits & 0o4000 != 0,
+            "setgid": bits & 0o2000 != 0,
+            "sticky": bits & 0o1000 != 0,
+        },
+        "owner": { "uid": metadata.uid(), "name": owner_name },
+        "group_owner": { "gid": metadata.gid(), "name": group_name },
+        "acl": has_acl,
+        "warnings": warnings,
+        "access": {
+            "uid": access.uid,
+            "user": access.user,
+            "as": access.role,
+            "read": access.read,
+            "write": access.write,
+            "execute": access.execute,
+            "blocked_by": access.blocked_by.map(|b| b.display().to_string()),
+        },
     })))
 }
+
+fn metadata_error(path: &Path, e: std::io::Error) -> EzError {
+    if e.kind() == std::io::ErrorKind::PermissionDenied {
+        if let Some(dir) = blocking_ancestor(path, &Identity::current()) {
+            return EzError::PermissionDenied(format!(
+                "Cannot read metadata for '{}': you cannot enter '{}' (no execute permission on that folder)",
+                path.display(), dir.display()
+            ));
+        }
+    }
+    if e.kind() == std::io::ErrorKind::NotFound {
+        EzError::NotFound(format!("Cannot read metadata for '{}': {}", path.display(), e))
+    } else if e.kind() == std::io::ErrorKind::PermissionDenied {
+        EzError::PermissionDenied(format!("Cannot read metadata for '{}': {}", path.display(), e))
+    } else {
+        EzError::General(format!("Cannot read metadata for '{}': {}", path.display(), e))
+    }
+}
+
+fn file_kind(ft: &fs::FileType) -> &'static str {
+    if ft.is_dir() {
+        "directory"
+    } else if ft.is_file() {
+        "regular file"
+    } else if ft.is_symlink() {
+        "symlink"
+    } else if ft.is_fifo() {
+        "named pipe"
+    } else if ft.is_socket() {
+        "socket"
+    } else if ft.is_block_device() {
+        "block device"
+    } else if ft.is_char_device() {
+        "character device"
+    } else {
+        "unknown"
+    }
+}
+
+/// Resolve a uid/gid from `/etc/passwd` or `/etc/group` (`name:x:id:...`).
+fn lookup_name(db: &str, id: u32) -> Option<String> {
+    let contents = fs::read_to_string(db).ok()?;
+    contents.lines()
+        .filter(|l| !l.starts_with('#'))
+        .find_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let found: u32 = fields.nth(1)?.parse().ok()?;
+            (found == id).then(|| name.to_string())
+        })
+}
+
+fn special_warnings(bits: u32, is_dir: bool) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if bits & 0o002 != 0 {
+        if is_dir && bits & 0o1000 == 0 {
+            warnings.push("World-writable folder without the sticky bit: anyone can delete or replace other people's files in it".to_string());
+        } else if !is_dir {
+            warnings.push("World-writable: any user on this machine can change this file".to_string());
+        }
+    }
+    if !is_dir && bits & 0o4000 != 0 {
+        warnings.push("Setuid: anyone who runs this gets the owner's privileges".to_string());
+    }
+    if !is_dir && bits & 0o2000 != 0 && bits & 0o010 != 0 {
+        warnings.push("Setgid: anyone who runs this gets the group's privileges".to_string());
+    }
+    if !is_dir && bits & 0o6000 != 0 && bits & 0o022 != 0 {
+        warnings.push("Privileged program is writable by others: it could be replaced with anything".to_string());
+    }
+    warnings
+}
+
+#[cfg(target_os = "linux")]
+fn has_acl(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let name = b"system.posix_acl_access\0";
+    // SAFETY: both strings are NUL-terminated and a null buffer of size 0 only queries the length
+    let len = unsafe {
+        libc::getxattr(c_path.as_ptr(), name.as_ptr() as *const libc::c_char, std::ptr::null_mut(), 0)
+    };
+    len > 0
+}
+
+#[cfg(not(target_os = "linux"))]
+fn has_acl(_path: &Path) -> bool {
+    false
+}
+
+/// What the current user can do with a file, judged from the mode bits.
+struct Access {
+    uid: u32,
+    user: Option<String>,
+    role: &'static str,
+    read: bool,
+    write: bool,
+    execute: bool,
+    blocked_by: Option<PathBuf>,
+}
+
+/// The effective user, group and supplementary groups of this process.
+struct Identity {
+    uid: u32,
+    gid: u32,
+    groups: Vec<u32>,
+}
+
+impl Identity {
+    fn current() -> Self {
+        // SAFETY: these calls have no preconditions; getgroups is sized by a first call
+        unsafe {
+            let uid = libc::geteuid();
+            let gid = libc::getegid();
+            let count = libc::getgroups(0, std::ptr::null_mut());
+            let mut groups = vec![0 as libc::gid_t; count.max(0) as usize];
+            let got = libc::getgroups(count, groups.as_mut_ptr());
+            groups.truncate(got.max(0) as usize);
+            Identity { uid, gid, groups }
+        }
+    }
+
+    fn role(&self, meta: &fs::Metadata) -> &'static str {
+        if self.uid == 0 {
+            "root"
+        } else if self.uid == meta.uid() {
+            "owner"
+        } else if self.gid == meta.gid() || self.groups.contains(&meta.gid()) {
+            "group member"
+        } else {
+            "other"
+        }
+    }
+
+    fn bits(&self, meta: &fs::Metadata) -> u32 {
+        let mode = meta.permissions().mode();
+        match self.role(meta) {
+            // root ignores read/write bits, and can execute if anyone can
+            "root" => 0o6 | if meta.is_dir() || mode & 0o111 != 0 { 0o1 } else { 0 },
+            "owner" => (mode >> 6) & 0o7,
+            "group member" => (mode >> 3) & 0o7,
+            _ => mode & 0o7,
+        }
+    }
+}
+
+/// The first folder on the way to `path` the user may not enter (no execute bit).
+fn blocking_ancestor(path: &Path, me: &Identity) -> Option<PathBuf> {
+    let absolute = match std::env::current_dir() {
+        Ok(cwd) if path.is_relative() => cwd.join(path),
+        _ => path.to_path_buf(),
+    };
+    let ancestors: Vec<&Path> = absolute.ancestors().skip(1).collect();
+    ancestors.into_iter().rev().find(|dir| {
+        fs::metadata(dir).map(|m| me.bits(&m) & 0o1 == 0).unwrap_or(false)
+    }).map(Path::to_path_buf)
+}
+
+fn check_access(path: &Path, meta: &fs::Metadata) -> Access {
+    let me = Identity::current();
+    let bits = me.bits(meta);
+
+    let blocked_by = blocking_ancestor(path, &me);
+    let reachable = blocked_by.is_none();
+
+    Access {
+        uid: me.uid,
+        user: lookup_name("/etc/passwd", me.uid),
+        role: me.role(meta),
+        read: reachable && bits & 0o4 != 0,
+        write: reachable && bits & 0o2 != 0,
+        execute: reachable && bits & 0o1 != 0,
+        blocked_by,
+    }
+}
+
+/// One comma-separated piece of a symbolic mode, e.g. `go-w` or `u+x`.
+struct Clause {
+    who: u32,
+    ops: Vec<(char, String)>,
+}
+
+/// A mode change given in octal (`755`) or symbolic (`u+x,go-w`) form.
+enum ModeChange {
+    Absolute(u32),
+    Symbolic(Vec<Clause>),
+}
+
+impl ModeChange {
+    fn parse(spec: &str) -> Result<Self, EzError> {
+        if !spec.is_empty() && spec.len() <= 4 && spec.chars().all(|c| c.is_digit(8)) {
+            return Ok(ModeChange::Absolute(u32::from_str_radix(spec, 8).unwrap()));
+        }
+
+        let invalid = || EzError::InvalidArgs(format!(
+            "Invalid mode '{}'. Use octal (755) or symbolic (u+x,go-w)", spec
+        ));
+        let mut clauses = Vec::new();
+        for part in spec.split(',') {
+            let who_len = part.find(|c| !"ugoa".contains(c)).unwrap_or(part.len());
+            let mut who = 0;
+            for c in part[..who_len].chars() {
+                who |= match c {
+                    'u' => 0o4700,
+                    'g' => 0o2070,
+                    'o' => 0o1007,
+                    _ => 0o7777,
+                };
+            }
+            if who == 0 {
+                who = 0o7777;
+            }
+
+            let mut ops = Vec::new();
+            let mut rest = &part[who_len..];
+            while let Some(op) = rest.chars().next() {
+                if !"+-=".contains(op) {
+                    return Err(invalid());
+                }
+                let perms_len = rest[1..].find(|c| "+-=".contains(c)).map(|i| i + 1).unwrap_or(rest.len());
+                let perms = &rest[1..perms_len];
+                if perms.chars().any(|c| !"rwxXst".contains(c)) {
+                    return Err(invalid());
+                }
+                ops.push((op, perms.to_string()));
+                rest = &rest[perms_len..];
+            }
+            if ops.is_empty() {
+                return Err(invalid());
+            }
+            clauses.push(Clause { who, ops });
+        }
+        Ok(ModeChange::Symbolic(clauses))
+    }
+
+    fn apply(&self, mode: u32, is_dir: bool) -> u32 {
+        let clauses = match self {
+            ModeChange::Absolute(m) => return *m,
+            ModeChange::Symbolic(c) => c,
+        };
+
+        let mut mode = mode & 0o7777;
+        for clause in clauses {
+            for (op, perms) in &clause.ops {
+                let mut bits = 0;
+                for c in perms.chars() {
+                    bits |= match c {
+                        'r' => 0o444,
+                        'w' => 0o222,
+                        'x' => 0o111,
+                        // Execute only for folders or files someone can already run
+                        'X' if is_dir || mode & 0o111 != 0 => 0o111,
+                        's' => 0o6000,
+                        't' => 0o1000,
+                        _ => 0,
+                    };
+                }
+                let bits = bits & clause.who;
+                mode = match op {
+                    '+' => mode | bits,
+                    '-' => mode & !bits,
+                    // Setuid/setgid on folders are kept unless named, like chmod
+                    _ => {
+                        let keep = if is_dir { 0o6000 & !bits } else { 0 };
+                        (mode & !clause.who) | bits | (mode & clause.who & keep)
+                    }
+                };
+            }
+        }
+        mode
+    }
+}
+
+/// `rwxr-xr-x` style string for the permission bits, including special bits.
+fn mode_string(mode: u32) -> String {
+    let bit = |mask: u32, c: char| if mode & mask != 0 { c } else { '-' };
+    let exec = |x: u32, special: u32, set: char| match (mode & x != 0, mode & special != 0) {
+        (true, true) => set,
+        (false, true) => set.to_ascii_uppercase(),
+        (true, false) => 'x',
+        (false, false) => '-',
+    };
+    [
+        bit(0o400, 'r'), bit(0o200, 'w'), exec(0o100, 0o4000, 's'),
+        bit(0o040, 'r'), bit(0o020, 'w'), exec(0o010, 0o2000, 's'),
+        bit(0o004, 'r'), bit(0o002, 'w'), exec(0o001, 0o1000, 't'),
+    ].iter().collect()
+}
+
+/// Plain-English summary, e.g. "owner can read and write; group and others can read".
+pub fn explain_mode(mode: u32) -> String {
+    let perms = |shift: u32| {
+        let bits = (mode >> shift) & 0o7;
+        let words: Vec<&str> = [(0o4, "read"), (0o2, "write"), (0o1, "execute")]
+            .iter()
+            .filter(|(b, _)| bits & b != 0)
+            .map(|(_, w)| *w)
+            .collect();
+        match words.len() {
+            0 => String::new(),
+            1 => words[0].to_string(),
+            n => format!("{} and {}", words[..n - 1].join(", "), words[n - 1]),
+        }
+    };
+    let classes = [("owner", perms(6)), ("group", perms(3)), ("others", perms(0))];
+
+    let mut groups: Vec<(Vec<&str>, &str)> = Vec::new();
+    for (name, p) in &classes {
+        match groups.iter_mut().find(|(_, gp)| *gp == p.as_str()) {
+            Some((names, _)) => names.push(name),
+            None => groups.push((vec![name], p.as_str())),
+        }
+    }
+
+    let mut parts: Vec<String> = groups.iter().map(|(names, p)| {
+        let who = if names.len() == 3 { "everyone".to_string() } else { names.join(" and ") };
+        if p.is_empty() {
+            if names.len() == 3 { "nobody has access".to_string() } else { format!("{} have no access", who) }
+        } else {
+            format!("{} can {}", who, p)
+        }
+    }).collect();
+
+    if mode & 0o4000 != 0 {
+        parts.push("runs as its owner (setuid)".to_string());
+    }
+    if mode & 0o2000 != 0 {
+        parts.push("runs as its group (setgid)".to_string());
+    }
+    if mode & 0o1000 != 0 {
+        parts.push("only owners may delete entries (sticky)".to_string());
+    }
+    parts.join("; ")
+}
+
+/// Change permissions. `changes` apply to everything, `files`/`dirs` replace
+/// them for that kind of entry when walking recursively.
+pub fn set(
+    paths: Vec<PathBuf>,
+    changes: Vec<String>,
+    recursive: bool,
+    files: Option<String>,
+    dirs: Option<String>,
+    ctx: &CommandContext,
+) -> Result<CommandOutput, EzError> {
+    if paths.is_empty() {
+        return Err(EzError::InvalidArgs("No paths given".to_string()));
+    }
+    if changes.is_empty() && files.is_none() && dirs.is_none() {
+        return Err(EzError::InvalidArgs(
+            "Nothing to change. Give a mode (755, u+x,go-w) or flags like --owner-write --no-others".to_string()
+        ));
+    }
+
+    let common = changes.iter().map(|c| ModeChange::parse(c)).collect::<Result<Vec<_>, _>>()?;
+    let file_changes = match files {
+        Some(spec) => vec![ModeChange::parse(&spec)?],
+        None => Vec::new(),
+    };
+    let dir_changes = match dirs {
+        Some(spec) => vec![ModeChange::parse(&spec)?],
+        None => Vec::new(),
+    };
+    let rules_for = |is_dir: bool| -> &[ModeChange] {
+        let specific = if is_dir { &dir_changes } else { &file_changes };
+        if specific.is_empty() { &common } else { specific }
+    };
+
+    let mut entries = Vec::new();
+    let mut changed = 0;
+
+    for path in &paths {
+        let meta = fs::metadata(path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                EzError::NotFound(format!("Cannot read metadata for '{}': {}", path.display(), e))
+            } else {
+                EzError::PermissionDenied(format!("Cannot read metadata for '{}': {}", path.display(), e))
+            }
+        })?;
+
+        let mut targets = vec![(path.clone(), meta.is_dir(), true)];
+        if recursive && meta.is_dir() {
+            // Symlinks are left alone: chmod on them would change whatever they point at
+            for entry in WalkDir::new(path).min_depth(1).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+                if entry.file_type().is_symlink() {
+                    continue;
+                }
+                targets.push((entry.path().to_path_buf(), entry.file_type().is_dir(), false));
+            }
+        }
+
+        for (target, is_dir, top_level) in targets {
+            let before = fs::metadata(&target)
+                .map_err(|e| EzError::General(format!("Cannot read metadata for '{}': {}", target.display(), e)))?
+                .permissions()
+                .mode() & 0o7777;
+            let after = rules_for(is_dir).iter().fold(before, |m, change| change.apply(m, is_dir));
+
+            if after != before {
+                changed += 1;
+                if !ctx.dry_run {
+                    set_mode(&target, after)?;
+                }
+            }
+            if !ctx.json {
+                print_change(&target, before, after, top_level);
+            }
+
+            entries.push(serde_json::json!({
+                "path": target.display().to_string(),
+                "type": if is_dir { "directory" } else { "file" },
+                "before": { "octal": format!("{:04o}", before), "string": mode_string(before), "explanation": explain_mode(before) },
+                "after": { "octal": format!("{:04o}", after), "string": mode_string(after), "explanation": explain_mode(after) },
+                "changed": after != before,
+            }));
+        }
+    }
+
+    if !ctx.json {
+        let verb = if ctx.dry_run { "Would change" } else { "Changed" };
+        let mark = if ctx.dry_run { "~".dimmed() } else { "✓".green() };
+        println!("\n{} {} permissions on {} of {} item(s)", mark, verb, changed, entries.len());
+    }
+
+    let mut metadata = serde_json::json!({ "changed": changed, "total": entries.len() });
+    if ctx.dry_run {
+        metadata["dry_run"] = serde_json::json!(true);
+    }
+
+    Ok(CommandOutput::new("permissions", serde_json::json!({ "entries": entries })).with_metadata(metadata))
+}
+
+fn set_mode(path: &Path, mode: u32) -> Result<(), EzError> {
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            EzError::PermissionDenied(format!("Cannot change permissions of '{}': {} (are you its owner?)", path.display(), e))
+        } else {
+            EzError::General(format!("Cannot change permissions of '{}': {}", path.display(), e))
+        }
+    })
+}
+
+fn print_change(path: &Path, before: u32, after: u32, detailed: bool) {
+    if !detailed {
+        if before != after {
+            println!("  {} {} {}  {}",
+                mode_string(before).dimmed(), "→".dimmed(), mode_string(after).yellow(), path.display());
+        }
+        return;
+    }
+
+    println!("{} {}", "📋".bold(), path.display().to_string().cyan());
+    if before == after {
+        println!("  {} {} ({:o}) — already set: {}", "unchanged:".dimmed(), mode_string(before), before, explain_mode(before));
+        return;
+    }
+    println!("  {} {} ({:o})  {}", "before:".dimmed(), mode_string(before), before, explain_mode(before).dimmed());
+    println!("  {}  {} ({:o})  {}", "after:".bold(), mode_string(after).yellow(), after, explain_mode(after));
+}
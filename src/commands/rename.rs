@@ -0,0 +1,434 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local};
+use colored::*;
+use regex::Regex;
+use crate::context::CommandContext;
+use crate::output::{CommandOutput, EzError};
+use crate::RenameCase;
+
+struct PlannedRename {
+    from: PathBuf,
+    to: PathBuf,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    pattern: String,
+    replacement: String,
+    paths: Vec<PathBuf>,
+    all: bool,
+    start: usize,
+    case: Option<RenameCase>,
+    ext: Option<String>,
+    ctx: &CommandContext,
+) -> Result<CommandOutput, EzError> {
+    if paths.is_empty() {
+        return Err(EzError::InvalidArgs("No files to rename. Pass paths, a glob, or pipe in `ez find` results".to_string()));
+    }
+
+    let re = Regex::new(&pattern)
+        .map_err(|e| EzError::InvalidArgs(format!("Invalid pattern: {}", e)))?;
+
+    let mut paths = crate::utils::expand_globs(paths);
+    paths.sort();
+    paths.dedup();
+
+    let mut plans = Vec::new();
+    let mut skipped = Vec::new();
+    let mut sequence = start;
+
+    for path in paths {
+        if fs::symlink_metadata(&path).is_err() {
+            return Err(EzError::NotFound(format!("'{}' does not exist", path.display())));
+        }
+        let name = match path.file_name() {
+            Some(n) => n.to_string_lossy().to_string(),
+            None => return Err(EzError::InvalidArgs(format!("Cannot rename '{}'", path.display()))),
+        };
+
+        if !re.is_match(&name) {
+            skipped.push(path.display().to_string());
+            continue;
+        }
+
+        let template = expand_placeholders(&replacement, &path, sequence)?;
+        sequence += 1;
+
+        let mut new_name = if all {
+            re.replace_all(&name, template.as_str()).to_string()
+        } else {
+            re.replace(&name, template.as_str()).to_string()
+        };
+        let (stem, extension) = split_extension(&new_name);
+        let extension = match &ext {
+            Some(e) => e.trim_start_matches('.').to_string(),
+            None => extension.to_string(),
+        };
+        let stem = match case {
+            Some(c) => apply_case(stem, c),
+            None => stem.to_string(),
+        };
+        new_name = if extension.is_empty() { stem } else { format!("{}.{}", stem, extension) };
+
+        if new_name.is_empty() || new_name == "." || new_name == ".." || new_name.contains('/') {
+            return Err(EzError::InvalidArgs(format!(
+                "'{}' would be renamed to invalid name '{}'", path.display(), new_name
+            )));
+        }
+
+        if new_name == name {
+            skipped.push(path.display().to_string());
+            continue;
+        }
+
+        let to = path.with_file_name(&new_name);
+        plans.push(PlannedRename { from: path, to });
+    }
+
+    check_collisions(&plans)?;
+    let cycles = count_cycles(&plans);
+
+    if !ctx.json {
+        print_table(&plans);
+        if cycles > 0 {
+            println!("{} {} rename cycle(s) detected; temporary names will be used", "ℹ".blue(), cycles);
+        }
+    }
+
+    if !ctx.dry_run && !plans.is_empty() {
+        apply(&plans)?;
+    }
+
+    if !ctx.json {
+        if ctx.dry_run {
+            println!("\n{} Would rename {} file(s), {} skipped", "~".dimmed(), plans.len(), skipped.len());
+        } else {
+            println!("\n{} Renamed {} file(s), {} skipped", "✓".green(), plans.len(), skipped.len());
+        }
+    }
+
+    let renames: Vec<_> = plans.iter().map(|p| serde_json::json!({
+        "from": p.from.display().to_string(),
+        "to": p.to.display().to_string(),
+    })).collect();
+    // Applying `undo` in order restores the original names
+    let undo: Vec<_> = plans.iter().rev().map(|p| serde_json::json!({
+        "from": p.to.display().to_string(),
+        "to": p.from.display().to_string(),
+    })).collect();
+
+    let mut metadata = serde_json::json!({
+        "total": plans.len(),
+        "cycles": cycles,
+    });
+    if ctx.dry_run {
+        metadata["dry_run"] = serde_json::json!(true);
+    }
+
+    Ok(CommandOutput::new("rename", serde_json::json!({
+        "renames": renames,
+        "skipped": skipped,
+        "undo": undo,
+    })).with_metadata(metadata))
+}
+
+/// Expand `{n}`, `{n:WIDTH}`, `{date}`, `{date:FORMAT}`, `{name}` and `{ext}`.
+/// Expanded values have `$` escaped so the regex replacement leaves them alone.
+fn expand_placeholders(template: &str, path: &Path, sequence: usize) -> Result<String, EzError> {
+    let mut out = String::new();
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        // `${name}` is a regex capture reference, not a placeholder
+        if rest[..open].ends_with('$') {
+            let close = rest[open..].find('}').map(|c| open + c + 1).unwrap_or(rest.len());
+            out.push_str(&rest[..close]);
+            rest = &rest[close..];
+            continue;
+        }
+
+        let close = match rest[open..].find('}') {
+            Some(c) => open + c,
+            None => break,
+        };
+        out.push_str(&rest[..open]);
+
+        let placeholder = &rest[open + 1..close];
+        let (key, arg) = match placeholder.split_once(':') {
+            Some((k, a)) => (k, Some(a)),
+            None => (placeholder, None),
+        };
+        let value = match key {
+            "n" => {
+                let width = match arg {
+                    Some(w) => w.parse::<usize>().map_err(|_| {
+                        EzError::InvalidArgs(format!("Invalid width in '{{{}}}'", placeholder))
+                    })?,
+                    None => 0,
+                };
+                format!("{:0width$}", sequence, width = width)
+            }
+            "date" => {
+                let modified = fs::symlink_metadata(path)
+                    .and_then(|m| m.modified())
+                    .map_err(|e| EzError::General(format!("Cannot read modified time of '{}': {}", path.display(), e)))?;
+                let datetime: DateTime<Local> = modified.into();
+                datetime.format(arg.unwrap_or("%Y-%m-%d")).to_string()
+            }
+            "name" => {
+                let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                split_extension(&name).0.to_string()
+            }
+            "ext" => {
+                let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                split_extension(&name).1.to_string()
+            }
+            _ => return Err(EzError::InvalidArgs(format!(
+                "Unknown placeholder '{{{}}}'. Use {{n}}, {{date}}, {{name}} or {{ext}}", placeholder
+            ))),
+        };
+        out.push_str(&value.replace('$', "$$"));
+        rest = &rest[close + 1..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Split "photo.tar.gz" into ("photo.tar", "gz"); dotfiles like ".env" have no extension.
+fn split_extension(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(0) | None => (name, ""),
+        Some(i) => (&name[..i], &name[i + 1..]),
+    }
+}
+
+fn apply_case(stem: &str, case: RenameCase) -> String {
+    match case {
+        RenameCase::Lower => stem.to_lowercase(),
+        RenameCase::Upper => stem.to_uppercase(),
+        RenameCase::Title => stem
+            .split_inclusive([' ', '_', '-'])
+            .map(|word| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase(),
+                    None => String::new(),
+                }
+            })
+            .collect(),
+        RenameCase::Snake => words(stem).join("_"),
+        RenameCase::Kebab => words(stem).join("-"),
+    }
+}
+
+/// Lowercased words of a name, split on separators and camelCase boundaries.
+fn words(stem: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+
+    for c in stem.chars() {
+        if c == ' ' || c == '_' || c == '-' || c == '.' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn check_collisions(plans: &[PlannedRename]) -> Result<(), EzError> {
+    let sources: HashSet<&PathBuf> = plans.iter().map(|p| &p.from).collect();
+    let mut targets: HashMap<&PathBuf, &PathBuf> = HashMap::new();
+
+    for plan in plans {
+        if let Some(other) = targets.insert(&plan.to, &plan.from) {
+            return Err(EzError::InvalidArgs(format!(
+                "Collision: both '{}' and '{}' would be renamed to '{}'",
+                other.display(), plan.from.display(), plan.to.display()
+            )));
+        }
+
+        if sources.contains(&plan.to) {
+            continue;
+        }
+        if let Ok(existing) = fs::symlink_metadata(&plan.to) {
+            // Case-only renames on case-insensitive filesystems point at the same inode
+            let same_file = fs::symlink_metadata(&plan.from)
+                .map(|m| m.dev() == existing.dev() && m.ino() == existing.ino())
+                .unwrap_or(false);
+            if !same_file {
+                return Err(EzError::InvalidArgs(format!(
+                    "Collision: '{}' would overwrite existing '{}'",
+                    plan.from.display(), plan.to.display()
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Count closed loops such as a → b → a among the planned renames.
+fn count_cycles(plans: &[PlannedRename]) -> usize {
+    let next: HashMap<&PathBuf, &PathBuf> = plans.iter().map(|p| (&p.from, &p.to)).collect();
+    let mut visited: HashSet<&PathBuf> = HashSet::new();
+    let mut cycles = 0;
+
+    for plan in plans {
+        let mut chain: Vec<&PathBuf> = Vec::new();
+        let mut current = &plan.from;
+        while !visited.contains(current) {
+            visited.insert(current);
+            chain.push(current);
+            match next.get(current) {
+                Some(&to) => current = to,
+                None => break,
+            }
+        }
+        if chain.contains(&current) && next.contains_key(current) {
+            cycles += 1;
+        }
+    }
+
+    cycles
+}
+
+/// Rename everything, going through temporary names when a target is also a
+/// source (chains and cycles). On failure, completed renames are rolled back.
+fn apply(plans: &[PlannedRename]) -> Result<(), EzError> {
+    let sources: HashSet<&PathBuf> = plans.iter().map(|p| &p.from).collect();
+    let needs_staging = plans.iter().any(|p| sources.contains(&p.to));
+
+    let steps: Vec<(PathBuf, PathBuf)> = if needs_staging {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let staged: Vec<PathBuf> = plans.iter().enumerate()
+            .map(|(i, p)| p.from.with_file_name(format!(".ez-rename-{}-{}", id, i)))
+            .collect();
+        plans.iter().zip(&staged).map(|(p, s)| (p.from.clone(), s.clone()))
+            .chain(plans.iter().zip(&staged).map(|(p, s)| (s.clone(), p.to.clone())))
+            .collect()
+    } else {
+        plans.iter().map(|p| (p.from.clone(), p.to.clone())).collect()
+    };
+
+    for (i, (from, to)) in steps.iter().enumerate() {
+        if let Err(e) = fs::rename(from, to) {
+            for (done_from, done_to) in steps[..i].iter().rev() {
+                let _ = fs::rename(done_to, done_from);
+            }
+            return Err(if e.kind() == std::io::ErrorKind::PermissionDenied {
+                EzError::PermissionDenied(format!("Cannot rename '{}': {} (changes rolled back)", from.display(), e))
+            } else {
+                EzError::General(format!("Cannot rename '{}': {} (changes rolled back)", from.display(), e))
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn print_table(plans: &[PlannedRename]) {
+    if plans.is_empty() {
+        println!("{} No files to rename", "ℹ".blue());
+        return;
+    }
+
+    let width = plans.iter()
+        .map(|p| p.from.display().to_string().chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Before".len());
+
+    println!("{:<width$}   {}", "Before".bold().underline(), "After".bold().underline(), width = width);
+    for plan in plans {
+        println!("{:<width$} → {}",
+            plan.from.display().to_string().dimmed(),
+            plan.to.display().to_string().green(),
+            width = width);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(dir: &Path, from: &str, to: &str) -> PlannedRename {
+        PlannedRename { from: dir.join(from), to: dir.join(to) }
+    }
+
+    #[test]
+    fn swap_cycle_goes_through_temporary_names() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a"), "A").unwrap();
+        fs::write(dir.path().join("b"), "B").unwrap();
+        let plans = [plan(dir.path(), "a", "b"), plan(dir.path(), "b", "a")];
+
+        assert!(check_collisions(&plans).is_ok());
+        assert_eq!(count_cycles(&plans), 1);
+        assert!(apply(&plans).is_ok());
+        assert_eq!(fs::read_to_string(dir.path().join("a")).unwrap(), "B");
+        assert_eq!(fs::read_to_string(dir.path().join("b")).unwrap(), "A");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn chains_are_not_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a"), "A").unwrap();
+        fs::write(dir.path().join("b"), "B").unwrap();
+        let plans = [plan(dir.path(), "a", "b"), plan(dir.path(), "b", "c")];
+
+        assert_eq!(count_cycles(&plans), 0);
+        assert!(apply(&plans).is_ok());
+        assert_eq!(fs::read_to_string(dir.path().join("b")).unwrap(), "A");
+        assert_eq!(fs::read_to_string(dir.path().join("c")).unwrap(), "B");
+        assert!(!dir.path().join("a").exists());
+    }
+
+    #[test]
+    fn existing_files_are_never_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a"), "A").unwrap();
+        fs::write(dir.path().join("taken"), "keep").unwrap();
+
+        let result = check_collisions(&[plan(dir.path(), "a", "taken")]);
+        assert!(matches!(result, Err(EzError::InvalidArgs(msg)) if msg.contains("overwrite existing")));
+    }
+
+    #[test]
+    fn two_sources_cannot_share_a_target() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a"), "A").unwrap();
+        fs::write(dir.path().join("b"), "B").unwrap();
+
+        let result = check_collisions(&[plan(dir.path(), "a", "c"), plan(dir.path(), "b", "c")]);
+        assert!(matches!(result, Err(EzError::InvalidArgs(msg)) if msg.contains("both")));
+    }
+
+    #[test]
+    fn failed_renames_are_rolled_back() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a"), "A").unwrap();
+        // "missing" doesn't exist, so the second step fails after the first succeeded
+        let plans = [plan(dir.path(), "a", "b"), plan(dir.path(), "missing", "c")];
+
+        assert!(apply(&plans).is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("a")).unwrap(), "A");
+        assert!(!dir.path().join("b").exists());
+    }
+}
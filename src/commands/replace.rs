@@ -1,78 +1,373 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use colored::*;
+use regex::Regex;
+use similar::{ChangeTag, TextDiff};
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
 
+/// Lines of surrounding context shown for each match in interactive mode.
+const HUNK_CONTEXT: usize = 2;
+
+/// One match in a file and the text it will be replaced with.
+struct Hunk {
+    start: usize,
+    end: usize,
+    replacement: String,
+}
+
+enum Decision {
+    Accept,
+    Skip,
+    Edit(String),
+    AcceptFile,
+    Quit,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
     old: String,
     new: String,
-    file: PathBuf,
+    files: Vec<PathBuf>,
     all: bool,
+    regex: bool,
+    no_ignore: bool,
+    interactive: bool,
+    backup: Option<String>,
     ctx: &CommandContext,
 ) -> Result<CommandOutput, EzError> {
-    let contents = fs::read_to_string(&file).map_err(|e| {
-        if e.kind() == std::io::ErrorKind::NotFound {
-            EzError::NotFound(format!("Cannot read '{}': {}", file.display(), e))
-        } else {
-            EzError::General(format!("Cannot read '{}': {}", file.display(), e))
-        }
-    })?;
+    if files.is_empty() {
+        return Err(EzError::InvalidArgs("No files given. Pass files, folders or globs, or pipe in paths".to_string()));
+    }
 
-    let (new_contents, count) = if all {
-        let count = contents.matches(&old).count();
-        (contents.replace(&old, &new), count)
+    // Prompts are pointless in a preview, and --yes means accept everything
+    let interactive = interactive && !ctx.dry_run && !ctx.yes;
+    if interactive && !ctx.should_confirm() {
+        return Err(EzError::Cancelled(
+            "Cannot ask about each match in non-interactive mode. Drop --interactive or use --yes.".to_string()
+        ));
+    }
+
+    let re = if regex {
+        Regex::new(&old)
     } else {
-        if let Some(pos) = contents.find(&old) {
-            let mut result = contents.clone();
-            result.replace_range(pos..pos + old.len(), &new);
-            (result, 1)
+        Regex::new(&regex::escape(&old))
+    }.map_err(|e| EzError::InvalidArgs(format!("Invalid pattern: {}", e)))?;
+
+    let files = crate::utils::collect_files(&crate::utils::expand_globs(files), !no_ignore);
+
+    let mut results = Vec::new();
+    let mut total = 0;
+    let mut scanned = 0;
+    let mut skipped_binary = Vec::new();
+    let mut skipped_not_utf8 = Vec::new();
+    let mut failed: Vec<(&PathBuf, EzError)> = Vec::new();
+    let mut quit = false;
+
+    for file in &files {
+        if quit {
+            break;
+        }
+
+        // One unreadable or unwritable file doesn't stop the others; each is reported at the end
+        let bytes = match fs::read(file) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                let error = if e.kind() == std::io::ErrorKind::NotFound {
+                    EzError::NotFound(format!("Cannot read '{}': {}", file.display(), e))
+                } else if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    EzError::PermissionDenied(format!("Cannot read '{}': {}", file.display(), e))
+                } else {
+                    EzError::General(format!("Cannot read '{}': {}", file.display(), e))
+                };
+                failed.push((file, error));
+                continue;
+            }
+        };
+
+        if crate::utils::is_binary(&bytes) {
+            skipped_binary.push(file.display().to_string());
+            continue;
+        }
+        let contents = match String::from_utf8(bytes) {
+            Ok(c) => c,
+            Err(_) => {
+                skipped_not_utf8.push(file.display().to_string());
+                continue;
+            }
+        };
+        scanned += 1;
+
+        let mut hunks = find_hunks(&re, &contents, &new, regex, all);
+        if hunks.is_empty() {
+            continue;
+        }
+
+        if interactive {
+            let (approved, stop) = review_hunks(file, &contents, hunks)?;
+            hunks = approved;
+            quit = stop;
+            if hunks.is_empty() {
+                continue;
+            }
+        }
+
+        let count = hunks.len();
+        let new_contents = apply_hunks(&contents, &hunks);
+        let mut entry = serde_json::json!({
+            "file": file.display().to_string(),
+            "replacements": count,
+        });
+
+        if ctx.dry_run {
+            let diff = crate::utils::unified_diff(file, &contents, &new_contents);
+            if !ctx.json {
+                crate::utils::print_diff(&diff);
+            }
+            entry["diff"] = serde_json::json!(diff);
         } else {
-            (contents.clone(), 0)
+            let backup_path = match crate::utils::write_atomic(file, new_contents.as_bytes(), backup.as_deref()) {
+                Ok(path) => path,
+                Err(e) => {
+                    let error = if e.kind() == std::io::ErrorKind::PermissionDenied {
+                        EzError::PermissionDenied(format!("Cannot write to '{}': {}", file.display(), e))
+                    } else {
+                        EzError::General(format!("Cannot write to '{}': {}", file.display(), e))
+                    };
+                    failed.push((file, error));
+                    continue;
+                }
+            };
+            if let Some(b) = backup_path {
+                entry["backup"] = serde_json::json!(b.display().to_string());
+            }
+            if !ctx.json {
+                println!("  {:>5} {}", count.to_string().cyan().bold(), file.display());
+            }
         }
-    };
 
-    if count == 0 {
-        if !ctx.json {
+        total += count;
+        results.push(entry);
+    }
+
+    if !ctx.json {
+        if total == 0 {
             println!("{} No matches found for '{}'", "ℹ️".yellow(), old.yellow());
+        } else if ctx.dry_run {
+            println!("{} Would replace {} occurrence(s) of '{}' with '{}' in {} file(s)",
+                "~".dimmed(), total, old.yellow(), new.green(), results.len());
+        } else {
+            println!("{} Replaced {} occurrence(s) of '{}' with '{}' in {} file(s)",
+                "✓".green(),
+                total.to_string().cyan().bold(),
+                old.yellow(),
+                new.green(),
+                results.len());
+        }
+        if !skipped_binary.is_empty() {
+            println!("{} Skipped {} binary file(s)", "~".dimmed(), skipped_binary.len());
+        }
+        if !skipped_not_utf8.is_empty() {
+            println!("{} Skipped {} file(s) that aren't UTF-8", "~".dimmed(), skipped_not_utf8.len());
+        }
+        if !failed.is_empty() {
+            println!("{} {} file(s) could not be changed:", "✗".red(), failed.len());
+            for (_, error) in &failed {
+                println!("  {}", error);
+            }
         }
-        return Ok(CommandOutput::new("replace", serde_json::json!({
-            "file": file.display().to_string(),
-            "replacements": 0,
-        })));
     }
 
+    let mut metadata = serde_json::json!({
+        "files_scanned": scanned,
+        "files_changed": results.len(),
+        "files_failed": failed.len(),
+        "skipped_binary": skipped_binary,
+        "skipped_not_utf8": skipped_not_utf8,
+        "mode": if regex { "regex" } else { "literal" },
+    });
     if ctx.dry_run {
-        if !ctx.json {
-            println!("{} Would replace {} occurrence(s) of '{}' with '{}' in {}",
-                "~".dimmed(), count, old.yellow(), new.green(), file.display().to_string().cyan());
-        }
-        return Ok(CommandOutput::new("replace", serde_json::json!({
-            "file": file.display().to_string(),
-            "replacements": count,
-        })).with_metadata(serde_json::json!({ "dry_run": true })));
+        metadata["dry_run"] = serde_json::json!(true);
+    }
+    if quit {
+        metadata["quit_early"] = serde_json::json!(true);
     }
 
-    fs::write(&file, new_contents).map_err(|e| {
-        if e.kind() == std::io::ErrorKind::PermissionDenied {
-            EzError::PermissionDenied(format!("Cannot write to '{}': {}", file.display(), e))
+    let exit_code = failed.first().map_or(0, |(_, e)| e.exit_code());
+    let mut output = CommandOutput::new("replace", serde_json::json!({
+        "files": results,
+        "replacements": total,
+        "failed": failed.iter().map(|(file, e)| serde_json::json!({
+            "file": file.display().to_string(),
+            "error": e.to_json(),
+        })).collect::<Vec<_>>(),
+    })).with_metadata(metadata).with_exit_code(exit_code);
+    output.success = failed.is_empty();
+    Ok(output)
+}
+
+/// Locate the first match (or every match with `all`) and work out its replacement.
+/// In regex mode `$1`/`${name}` in `new` expand to captures; otherwise it's literal.
+fn find_hunks(re: &Regex, contents: &str, new: &str, regex: bool, all: bool) -> Vec<Hunk> {
+    let mut hunks = Vec::new();
+    for caps in re.captures_iter(contents) {
+        let m = caps.get(0).unwrap();
+        let replacement = if regex {
+            let mut expanded = String::new();
+            caps.expand(new, &mut expanded);
+            expanded
         } else {
-            EzError::General(format!("Cannot write to '{}': {}", file.display(), e))
+            new.to_string()
+        };
+        hunks.push(Hunk { start: m.start(), end: m.end(), replacement });
+        if !all {
+            break;
         }
-    })?;
+    }
+    hunks
+}
 
-    if !ctx.json {
-        println!("{} Replaced {} occurrence(s) of '{}' with '{}' in {}",
-            "✓".green(),
-            count.to_string().cyan().bold(),
-            old.yellow(),
-            new.green(),
-            file.display().to_string().cyan()
-        );
-    }
-
-    Ok(CommandOutput::new("replace", serde_json::json!({
-        "file": file.display().to_string(),
-        "replacements": count,
-    })))
+fn apply_hunks(contents: &str, hunks: &[Hunk]) -> String {
+    let mut result = String::with_capacity(contents.len());
+    let mut last = 0;
+    for hunk in hunks {
+        result.push_str(&contents[last..hunk.start]);
+        result.push_str(&hunk.replacement);
+        last = hunk.end;
+    }
+    result.push_str(&contents[last..]);
+    result
+}
+
+/// Walk the user through each match. Returns the approved hunks and whether they chose to quit.
+fn review_hunks(file: &Path, contents: &str, hunks: Vec<Hunk>) -> Result<(Vec<Hunk>, bool), EzError> {
+    let total = hunks.len();
+    let mut approved = Vec::new();
+    let mut accept_rest = false;
+
+    for (i, mut hunk) in hunks.into_iter().enumerate() {
+        if accept_rest {
+            approved.push(hunk);
+            continue;
+        }
+
+        show_hunk(file, contents, &hunk, i + 1, total);
+        match prompt_decision(&hunk)? {
+            Decision::Accept => approved.push(hunk),
+            Decision::Skip => {}
+            Decision::Edit(text) => {
+                hunk.replacement = text;
+                approved.push(hunk);
+            }
+            Decision::AcceptFile => {
+                accept_rest = true;
+                approved.push(hunk);
+            }
+            Decision::Quit => return Ok((approved, true)),
+        }
+    }
+
+    Ok((approved, false))
+}
+
+fn show_hunk(file: &Path, contents: &str, hunk: &Hunk, index: usize, total: usize) {
+    let line = contents[..hunk.start].matches('\n').count() + 1;
+    let (lo, hi) = context_bounds(contents, hunk.start, hunk.end, HUNK_CONTEXT);
+    let before = &contents[lo..hi];
+    let after = format!("{}{}{}", &contents[lo..hunk.start], hunk.replacement, &contents[hunk.end..hi]);
+
+    println!("\n{} {}",
+        format!("{}:{}", file.display(), line).cyan().bold(),
+        format!("({}/{})", index, total).dimmed());
+
+    let diff = TextDiff::from_lines(before, after.as_str());
+    for change in diff.iter_all_changes() {
+        let text = change.to_string_lossy();
+        let text = text.trim_end_matches('\n');
+        match change.tag() {
+            ChangeTag::Delete => println!("{}", format!("-{}", text).red()),
+            ChangeTag::Insert => println!("{}", format!("+{}", text).green()),
+            ChangeTag::Equal => println!(" {}", text.dimmed()),
+        }
+    }
+}
+
+fn prompt_decision(hunk: &Hunk) -> Result<Decision, EzError> {
+    loop {
+        print!("Replace this? [y]es [n]o [e]dit [a]ll in file [q]uit: ");
+        std::io::stdout().flush().unwrap();
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+
+        match input.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(Decision::Accept),
+            "n" | "no" | "" => return Ok(Decision::Skip),
+            "a" | "all" => return Ok(Decision::AcceptFile),
+            "q" | "quit" => return Ok(Decision::Quit),
+            "e" | "edit" => {
+                let text: String = dialoguer::Input::new()
+                    .with_prompt("Replace with")
+                    .with_initial_text(hunk.replacement.clone())
+                    .allow_empty(true)
+                    .interact_text()
+                    .map_err(|e| EzError::General(format!("Cannot read input: {}", e)))?;
+                return Ok(Decision::Edit(text));
+            }
+            _ => println!("{}", "Please answer y, n, e, a or q".yellow()),
+        }
+    }
+}
+
+/// Byte range covering the lines of `start..end` plus `lines` lines either side.
+fn context_bounds(contents: &str, start: usize, end: usize, lines: usize) -> (usize, usize) {
+    let mut lo = contents[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    for _ in 0..lines {
+        if lo == 0 {
+            break;
+        }
+        lo = contents[..lo - 1].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    }
+
+    let mut hi = contents[end..].find('\n').map(|i| end + i + 1).unwrap_or(contents.len());
+    for _ in 0..lines {
+        if hi >= contents.len() {
+            break;
+        }
+        hi = contents[hi..].find('\n').map(|i| hi + i + 1).unwrap_or(contents.len());
+    }
+
+    (lo, hi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replace(pattern: &str, contents: &str, new: &str, regex: bool, all: bool) -> String {
+        let re = Regex::new(&if regex { pattern.to_string() } else { regex::escape(pattern) }).unwrap();
+        apply_hunks(contents, &find_hunks(&re, contents, new, regex, all))
+    }
+
+    #[test]
+    fn first_match_only_unless_all() {
+        assert_eq!(replace("a.b", "a.b axb a.b", "X", false, false), "X axb a.b");
+        assert_eq!(replace("a.b", "a.b axb a.b", "X", false, true), "X axb X");
+    }
+
+    #[test]
+    fn captures_expand_only_in_regex_mode() {
+        assert_eq!(replace(r"(\w+)@(\w+)", "me@host", "$2:$1", true, true), "host:me");
+        assert_eq!(replace("me", "me@host", "$1", false, true), "$1@host");
+    }
+
+    #[test]
+    fn context_covers_whole_lines() {
+        let text = "one\ntwo\nthree\nfour\nfive\n";
+        let start = text.find("three").unwrap();
+        let (lo, hi) = context_bounds(text, start, start + 5, 1);
+        assert_eq!(&text[lo..hi], "two\nthree\nfour\n");
+        let (lo, hi) = context_bounds(text, 0, 3, 2);
+        assert_eq!(&text[lo..hi], "one\ntwo\nthree\n");
+    }
 }
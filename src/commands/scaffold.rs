@@ -0,0 +1,177 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use colored::*;
+use serde_yaml::Value;
+use crate::commands::create_file::{load_template, render_template};
+use crate::context::CommandContext;
+use crate::output::{CommandOutput, EzError};
+
+/// Something the spec asks for, in creation order (parents before children).
+enum Entry {
+    Folder(PathBuf),
+    File(PathBuf, String),
+}
+
+/// Create a folder tree from a YAML (or JSON) spec. Keys ending in `/` are
+/// folders holding more entries; other keys are files whose value is their
+/// content, null for empty, or `{ template: NAME }` / `{ content: TEXT }`.
+///
+/// ```yaml
+/// src/:
+///   main.py: { template: python-cli }
+///   utils/:
+/// README.md: "# {{name}}"
+/// .gitignore:
+/// ```
+pub fn execute(spec: PathBuf, to: PathBuf, if_not_exists: bool, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    let text = fs::read_to_string(&spec).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            EzError::NotFound(format!("Cannot read '{}': {}", spec.display(), e))
+        } else {
+            EzError::General(format!("Cannot read '{}': {}", spec.display(), e))
+        }
+    })?;
+    let root: Value = serde_yaml::from_str(&text)
+        .map_err(|e| EzError::InvalidArgs(format!("Cannot parse '{}': {}", spec.display(), e)))?;
+
+    let mut entries = Vec::new();
+    collect_entries(&root, &to, &mut entries)?;
+
+    // Check everything up front so a clash doesn't leave a half-built tree
+    let mut skipped = Vec::new();
+    for entry in &entries {
+        if let Entry::File(path, _) = entry {
+            if path.exists() {
+                if !if_not_exists {
+                    return Err(EzError::InvalidArgs(format!(
+                        "'{}' already exists. Use --if-not-exists to keep existing files", path.display()
+                    )));
+                }
+                skipped.push(path.display().to_string());
+            }
+        } else if let Entry::Folder(path) = entry {
+            if path.exists() && !path.is_dir() {
+                return Err(EzError::InvalidArgs(format!("'{}' exists and is not a folder", path.display())));
+            }
+        }
+    }
+
+    let mut folders = Vec::new();
+    let mut files = Vec::new();
+
+    for entry in &entries {
+        match entry {
+            Entry::Folder(path) => {
+                if path.is_dir() {
+                    continue;
+                }
+                if !ctx.dry_run {
+                    fs::create_dir_all(path).map_err(|e| {
+                        if e.kind() == std::io::ErrorKind::PermissionDenied {
+                            EzError::PermissionDenied(format!("Cannot create folder '{}': {}", path.display(), e))
+                        } else {
+                            EzError::General(format!("Cannot create folder '{}': {}", path.display(), e))
+                        }
+                    })?;
+                }
+                if !ctx.json {
+                    let verb = if ctx.dry_run { "Would create" } else { "Created" };
+                    println!("{} {} folder '{}'", mark(ctx), verb, path.display().to_string().blue().bold());
+                }
+                folders.push(path.display().to_string());
+            }
+            Entry::File(path, contents) => {
+                if path.exists() {
+                    if !ctx.json {
+                        println!("{} Skipped '{}' (already exists)", "~".dimmed(), path.display());
+                    }
+                    continue;
+                }
+                if !ctx.dry_run {
+                    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                        fs::create_dir_all(parent)
+                            .map_err(|e| EzError::General(format!("Cannot create folder '{}': {}", parent.display(), e)))?;
+                    }
+                    fs::write(path, contents).map_err(|e| {
+                        if e.kind() == std::io::ErrorKind::PermissionDenied {
+                            EzError::PermissionDenied(format!("Cannot create file '{}': {}", path.display(), e))
+                        } else {
+                            EzError::General(format!("Cannot create file '{}': {}", path.display(), e))
+                        }
+                    })?;
+                }
+                if !ctx.json {
+                    let verb = if ctx.dry_run { "Would create" } else { "Created" };
+                    println!("{} {} file '{}'", mark(ctx), verb, path.display());
+                }
+                files.push(path.display().to_string());
+            }
+        }
+    }
+
+    if !ctx.json {
+        let verb = if ctx.dry_run { "Would create" } else { "Created" };
+        println!("\n{} {} {} folder(s) and {} file(s)", mark(ctx), verb, folders.len(), files.len());
+    }
+
+    let mut metadata = serde_json::json!({ "spec": spec.display().to_string() });
+    if ctx.dry_run {
+        metadata["dry_run"] = serde_json::json!(true);
+    }
+
+    Ok(CommandOutput::new("scaffold", serde_json::json!({
+        "folders": folders,
+        "files": files,
+        "skipped": skipped,
+    })).with_metadata(metadata))
+}
+
+fn mark(ctx: &CommandContext) -> ColoredString {
+    if ctx.dry_run { "~".dimmed() } else { "✓".green() }
+}
+
+fn collect_entries(node: &Value, base: &Path, entries: &mut Vec<Entry>) -> Result<(), EzError> {
+    let map = match node {
+        Value::Mapping(m) => m,
+        Value::Null => return Ok(()),
+        _ => return Err(EzError::InvalidArgs(format!(
+            "Expected a mapping of names under '{}'", base.display()
+        ))),
+    };
+
+    for (key, value) in map {
+        let key = key.as_str().ok_or_else(|| {
+            EzError::InvalidArgs(format!("Entry names under '{}' must be strings", base.display()))
+        })?;
+        if key.split('/').any(|part| part == "..") || Path::new(key).is_absolute() {
+            return Err(EzError::InvalidArgs(format!("'{}' must stay inside the target folder", key)));
+        }
+
+        if let Some(folder) = key.strip_suffix('/') {
+            let path = base.join(folder);
+            entries.push(Entry::Folder(path.clone()));
+            collect_entries(value, &path, entries)?;
+            continue;
+        }
+
+        let path = base.join(key);
+        let contents = match value {
+            Value::Null => String::new(),
+            Value::String(s) => render_template(s, &path),
+            Value::Mapping(m) => {
+                let field = |name: &str| m.get(Value::String(name.to_string())).and_then(|v| v.as_str());
+                match (field("template"), field("content")) {
+                    (Some(template), _) => render_template(&load_template(template)?, &path),
+                    (None, Some(content)) => render_template(content, &path),
+                    (None, None) => return Err(EzError::InvalidArgs(format!(
+                        "'{}' needs a template or content (end the name with '/' for a folder)", key
+                    ))),
+                }
+            }
+            other => render_template(&serde_yaml::to_string(other).unwrap_or_default(), &path),
+        };
+        entries.push(Entry::File(path, contents));
+    }
+
+    Ok(())
+}
@@ -68,13 +68,32 @@ fn build_registry() -> Vec<CommandSchema> {
             aliases: vec![],
             description: "Find files or text",
             args: vec![
-                ArgSchema { name: "pattern", description: "What to find", required: true, default: None, multiple: false },
+                ArgSchema { name: "pattern", description: "What to find ('*' matches everything)", required: true, default: None, multiple: false },
                 ArgSchema { name: "path", description: "Where to look", required: false, default: Some("."), multiple: false },
             ],
             flags: vec![
-                FlagSchema { short: Some('i'), long: "inside", description: "Find in file contents", takes_value: false },
-                FlagSchema { short: Some('c'), long: "ignore-case", description: "Case insensitive search", takes_value: false },
+                FlagSchema { short: None, long: "inside", description: "Find in file contents", takes_value: false },
+                FlagSchema { short: Some('i'), long: "ignore-case", description: "Case insensitive search", takes_value: false },
                 FlagSchema { short: Some('n'), long: "line-numbers", description: "Show line numbers for content matches", takes_value: false },
+                FlagSchema { short: None, long: "glob", description: "Treat the pattern as a glob like *.rs", takes_value: false },
+                FlagSchema { short: None, long: "regex", description: "Treat the pattern as a regular expression", takes_value: false },
+                FlagSchema { short: Some('t'), long: "type", description: "Only files, dirs or symlinks", takes_value: true },
+                FlagSchema { short: Some('e'), long: "ext", description: "Only these extensions (comma-separated)", takes_value: true },
+                FlagSchema { short: None, long: "min-size", description: "Only files at least this big (e.g. 10KB)", takes_value: true },
+                FlagSchema { short: None, long: "max-size", description: "Only files at most this big (e.g. 5MB)", takes_value: true },
+                FlagSchema { short: None, long: "newer", description: "Changed within this long (2d, 3h) or since a date", takes_value: true },
+                FlagSchema { short: None, long: "older", description: "Not changed for this long or since a date", takes_value: true },
+                FlagSchema { short: None, long: "created", description: "Use creation time for --newer/--older", takes_value: false },
+                FlagSchema { short: Some('d'), long: "depth", description: "How many folders deep to look", takes_value: true },
+                FlagSchema { short: Some('H'), long: "hidden", description: "Include hidden files and folders", takes_value: false },
+                FlagSchema { short: None, long: "no-ignore", description: "Don't skip files listed in .gitignore/.ignore", takes_value: false },
+                FlagSchema { short: None, long: "exec", description: "Run a command per match ({} is the path)", takes_value: true },
+                FlagSchema { short: None, long: "exec-batch", description: "Run a command with all matches, batched to fit the argument limit", takes_value: true },
+                FlagSchema { short: None, long: "delete", description: "Delete every match (asks first)", takes_value: false },
+                FlagSchema { short: None, long: "copy-to", description: "Copy matches into a folder", takes_value: true },
+                FlagSchema { short: None, long: "pack-into", description: "Pack matches into an archive", takes_value: true },
+                FlagSchema { short: None, long: "chmod", description: "Change permissions of matches", takes_value: true },
+                FlagSchema { short: None, long: "threads", description: "Worker threads for walking folders (default: one per CPU)", takes_value: true },
             ],
         },
         CommandSchema {
@@ -82,20 +101,97 @@ fn build_registry() -> Vec<CommandSchema> {
             aliases: vec!["grep"],
             description: "Search for text in files recursively",
             args: vec![
-                ArgSchema { name: "pattern", description: "Pattern to search for", required: true, default: None, multiple: false },
+                ArgSchema { name: "pattern", description: "Text to search for (a regex with --regex)", required: true, default: None, multiple: false },
                 ArgSchema { name: "path", description: "Where to search", required: false, default: Some("."), multiple: false },
             ],
             flags: vec![
                 FlagSchema { short: Some('c'), long: "context", description: "Number of context lines", takes_value: true },
+                FlagSchema { short: Some('i'), long: "ignore-case", description: "Case insensitive search", takes_value: false },
+                FlagSchema { short: Some('S'), long: "smart-case", description: "Case insensitive unless the pattern has uppercase", takes_value: false },
+                FlagSchema { short: Some('w'), long: "word-regexp", description: "Only match whole words", takes_value: false },
+                FlagSchema { short: Some('v'), long: "invert-match", description: "Show lines that do not match", takes_value: false },
+                FlagSchema { short: Some('E'), long: "regex", description: "Treat the pattern as a regular expression", takes_value: false },
+                FlagSchema { short: Some('U'), long: "multiline", description: "Let the pattern match across lines", takes_value: false },
+                FlagSchema { short: Some('l'), long: "files-only", description: "Only list matching files", takes_value: false },
+                FlagSchema { short: None, long: "count", description: "Count matching lines per file", takes_value: false },
+                FlagSchema { short: None, long: "include", description: "Only search files matching a glob (repeatable)", takes_value: true },
+                FlagSchema { short: None, long: "exclude", description: "Skip files and folders matching a glob (repeatable)", takes_value: true },
+                FlagSchema { short: Some('z'), long: "archives", description: "Search inside .gz/.bz2 files and .zip/.tar archives", takes_value: false },
+                FlagSchema { short: None, long: "indexed", description: "Only read files the trigram index says could match", takes_value: false },
+                FlagSchema { short: None, long: "threads", description: "Worker threads for walking folders (default: one per CPU)", takes_value: true },
+            ],
+        },
+        CommandSchema {
+            name: "outline",
+            aliases: vec!["symbols"],
+            description: "List functions, types and headings in Rust, Python, JS/TS, Go and Markdown files",
+            args: vec![ArgSchema { name: "path", description: "File or folder to outline", required: false, default: Some("."), multiple: false }],
+            flags: vec![
+                FlagSchema { short: None, long: "threads", description: "Worker threads for walking folders (default: one per CPU)", takes_value: true },
+            ],
+        },
+        CommandSchema {
+            name: "todos",
+            aliases: vec!["todo"],
+            description: "Collect TODO/FIXME/HACK/XXX comments with owners and issue numbers",
+            args: vec![ArgSchema { name: "path", description: "Folder or file to scan", required: false, default: Some("."), multiple: false }],
+            flags: vec![
+                FlagSchema { short: Some('t'), long: "tag", description: "Tag to look for (repeatable; replaces the defaults)", takes_value: true },
+                FlagSchema { short: Some('g'), long: "group-by", description: "Group by file, tag or owner", takes_value: true },
+                FlagSchema { short: None, long: "threads", description: "Worker threads for walking folders (default: one per CPU)", takes_value: true },
+            ],
+        },
+        CommandSchema {
+            name: "secrets",
+            aliases: vec![],
+            description: "Find likely credentials; exits with code 6 when any are found",
+            args: vec![ArgSchema { name: "path", description: "Folder or file to scan", required: false, default: Some("."), multiple: false }],
+            flags: vec![
+                FlagSchema { short: None, long: "allowlist", description: "File of fingerprints, path:GLOB and rule:ID entries to skip", takes_value: true },
+                FlagSchema { short: None, long: "gitignore", description: "Skip files git ignores", takes_value: false },
+                FlagSchema { short: None, long: "threads", description: "Worker threads for walking folders (default: one per CPU)", takes_value: true },
+            ],
+        },
+        CommandSchema {
+            name: "index build",
+            aliases: vec![],
+            description: "Build or refresh the trigram index used by search --indexed",
+            args: vec![ArgSchema { name: "path", description: "Folder to index", required: false, default: Some("."), multiple: false }],
+            flags: vec![
+                FlagSchema { short: None, long: "threads", description: "Worker threads for walking folders (default: one per CPU)", takes_value: true },
             ],
         },
         CommandSchema {
             name: "permissions",
             aliases: vec!["perms"],
-            description: "Show file permissions",
+            description: "Show permissions, special bits, ownership and what you can do with a file",
             args: vec![ArgSchema { name: "path", description: "File or directory to check", required: true, default: None, multiple: false }],
             flags: vec![],
         },
+        CommandSchema {
+            name: "permissions set",
+            aliases: vec!["perms set"],
+            description: "Change permissions with a plain-English before/after",
+            args: vec![ArgSchema { name: "paths", description: "Files or folders to change", required: true, default: None, multiple: true }],
+            flags: vec![
+                FlagSchema { short: Some('m'), long: "mode", description: "Octal (755) or symbolic (u+x,go-w) mode", takes_value: true },
+                FlagSchema { short: Some('r'), long: "recursive", description: "Apply inside folders too", takes_value: false },
+                FlagSchema { short: None, long: "files", description: "Mode for files only", takes_value: true },
+                FlagSchema { short: None, long: "dirs", description: "Mode for folders only", takes_value: true },
+                FlagSchema { short: None, long: "owner-read", description: "Let the owner read", takes_value: false },
+                FlagSchema { short: None, long: "owner-write", description: "Let the owner write", takes_value: false },
+                FlagSchema { short: None, long: "owner-exec", description: "Let the owner execute", takes_value: false },
+                FlagSchema { short: None, long: "group-read", description: "Let the group read", takes_value: false },
+                FlagSchema { short: None, long: "group-write", description: "Let the group write", takes_value: false },
+                FlagSchema { short: None, long: "group-exec", description: "Let the group execute", takes_value: false },
+                FlagSchema { short: None, long: "others-read", description: "Let others read", takes_value: false },
+                FlagSchema { short: None, long: "others-write", description: "Let others write", takes_value: false },
+                FlagSchema { short: None, long: "others-exec", description: "Let others execute", takes_value: false },
+                FlagSchema { short: None, long: "no-owner", description: "Clear all owner permissions first", takes_value: false },
+                FlagSchema { short: None, long: "no-group", description: "Clear all group permissions first", takes_value: false },
+                FlagSchema { short: None, long: "no-others", description: "Clear all others' permissions first", takes_value: false },
+            ],
+        },
         CommandSchema {
             name: "tree",
             aliases: vec![],
@@ -103,6 +199,7 @@ fn build_registry() -> Vec<CommandSchema> {
             args: vec![ArgSchema { name: "path", description: "Root directory", required: false, default: Some("."), multiple: false }],
             flags: vec![
                 FlagSchema { short: Some('d'), long: "depth", description: "Maximum depth to display", takes_value: true },
+                FlagSchema { short: None, long: "threads", description: "Worker threads for walking folders (default: one per CPU)", takes_value: true },
             ],
         },
         CommandSchema {
@@ -149,10 +246,45 @@ fn build_registry() -> Vec<CommandSchema> {
             args: vec![
                 ArgSchema { name: "old", description: "Text to find", required: true, default: None, multiple: false },
                 ArgSchema { name: "new", description: "Text to replace with", required: true, default: None, multiple: false },
-                ArgSchema { name: "file", description: "File to modify", required: true, default: None, multiple: false },
+                ArgSchema { name: "files", description: "Files, folders or globs to modify", required: true, default: None, multiple: true },
             ],
             flags: vec![
                 FlagSchema { short: Some('a'), long: "all", description: "Replace all occurrences", takes_value: false },
+                FlagSchema { short: None, long: "regex", description: "Treat the text to find as a regular expression", takes_value: false },
+                FlagSchema { short: None, long: "no-ignore", description: "Don't skip files excluded by .gitignore", takes_value: false },
+                FlagSchema { short: Some('i'), long: "interactive", description: "Ask before each replacement", takes_value: false },
+                FlagSchema { short: None, long: "backup", description: "Keep a copy of each original (optional =SUFFIX, default .bak)", takes_value: true },
+            ],
+        },
+        CommandSchema {
+            name: "edit",
+            aliases: vec![],
+            description: "Make precise line-based edits to a file",
+            args: vec![ArgSchema { name: "file", description: "File to edit", required: true, default: None, multiple: false }],
+            flags: vec![
+                FlagSchema { short: None, long: "insert-after", description: "Insert text after line N", takes_value: true },
+                FlagSchema { short: None, long: "insert-after-match", description: "Insert text after the first line matching a regex", takes_value: true },
+                FlagSchema { short: None, long: "delete-lines", description: "Delete a line range like 10-20", takes_value: true },
+                FlagSchema { short: None, long: "between", description: "Replace everything between START and END marker lines", takes_value: true },
+                FlagSchema { short: None, long: "append-if-missing", description: "Append a line unless already present", takes_value: true },
+                FlagSchema { short: None, long: "hash", description: "Print the file's SHA-256", takes_value: false },
+                FlagSchema { short: Some('t'), long: "text", description: "Text to insert (or stdin)", takes_value: true },
+                FlagSchema { short: None, long: "expect-hash", description: "Refuse to edit unless the SHA-256 matches", takes_value: true },
+                FlagSchema { short: None, long: "backup", description: "Keep a copy of the original (optional =SUFFIX, default .bak)", takes_value: true },
+            ],
+        },
+        CommandSchema {
+            name: "config-edit",
+            aliases: vec![],
+            description: "Get, set or delete keys in JSON, TOML and YAML files",
+            args: vec![
+                ArgSchema { name: "file", description: "Config file to read or edit", required: true, default: None, multiple: false },
+                ArgSchema { name: "action", description: "get, set or delete", required: true, default: None, multiple: false },
+                ArgSchema { name: "expression", description: "Key path like server.ports[0] (KEY=VALUE for set)", required: true, default: None, multiple: false },
+            ],
+            flags: vec![
+                FlagSchema { short: Some('f'), long: "format", description: "File format (json, toml, yaml)", takes_value: true },
+                FlagSchema { short: None, long: "backup", description: "Keep a copy of the original (optional =SUFFIX, default .bak)", takes_value: true },
             ],
         },
         CommandSchema {
@@ -181,6 +313,22 @@ fn build_registry() -> Vec<CommandSchema> {
                 FlagSchema { short: None, long: "if-not-exists", description: "Skip if destination exists", takes_value: false },
             ],
         },
+        CommandSchema {
+            name: "rename",
+            aliases: vec![],
+            description: "Rename many files at once using a pattern",
+            args: vec![
+                ArgSchema { name: "pattern", description: "Regex to match in file names", required: true, default: None, multiple: false },
+                ArgSchema { name: "replacement", description: "Replacement with $1, {n}, {date}, {name}, {ext}", required: true, default: None, multiple: false },
+                ArgSchema { name: "paths", description: "Files to rename (globs allowed)", required: true, default: None, multiple: true },
+            ],
+            flags: vec![
+                FlagSchema { short: Some('a'), long: "all", description: "Replace every match in the name", takes_value: false },
+                FlagSchema { short: None, long: "start", description: "First number used for {n}", takes_value: true },
+                FlagSchema { short: None, long: "case", description: "Case of the new name (lower, upper, title, snake, kebab)", takes_value: true },
+                FlagSchema { short: None, long: "ext", description: "Change the extension", takes_value: true },
+            ],
+        },
         CommandSchema {
             name: "remove",
             aliases: vec!["rm"],
@@ -208,6 +356,21 @@ fn build_registry() -> Vec<CommandSchema> {
             args: vec![ArgSchema { name: "paths", description: "File paths to create", required: true, default: None, multiple: true }],
             flags: vec![
                 FlagSchema { short: None, long: "if-not-exists", description: "Return success without truncating if exists", takes_value: false },
+                FlagSchema { short: None, long: "template", description: "Fill the file from a template in ~/.config/ez/templates", takes_value: true },
+                FlagSchema { short: None, long: "content", description: "Initial content (- reads stdin)", takes_value: true },
+                FlagSchema { short: None, long: "time", description: "Set access/modification time", takes_value: true },
+                FlagSchema { short: None, long: "reference", description: "Copy times from another file", takes_value: true },
+                FlagSchema { short: None, long: "mode", description: "Octal permissions set at creation", takes_value: true },
+            ],
+        },
+        CommandSchema {
+            name: "scaffold",
+            aliases: vec![],
+            description: "Create folders and files from a YAML or JSON spec",
+            args: vec![ArgSchema { name: "spec", description: "Spec file (names ending in / are folders)", required: true, default: None, multiple: false }],
+            flags: vec![
+                FlagSchema { short: None, long: "to", description: "Folder to create the tree in", takes_value: true },
+                FlagSchema { short: None, long: "if-not-exists", description: "Keep existing files instead of failing", takes_value: false },
             ],
         },
         CommandSchema {
@@ -224,6 +387,7 @@ fn build_registry() -> Vec<CommandSchema> {
             args: vec![ArgSchema { name: "path", description: "Path to check", required: false, default: Some("."), multiple: false }],
             flags: vec![
                 FlagSchema { short: Some('d'), long: "detailed", description: "Show detailed breakdown", takes_value: false },
+                FlagSchema { short: None, long: "threads", description: "Worker threads for walking folders (default: one per CPU)", takes_value: true },
             ],
         },
         CommandSchema {
@@ -276,6 +440,25 @@ fn build_registry() -> Vec<CommandSchema> {
                 FlagSchema { short: Some('t'), long: "to", description: "Where to unpack", takes_value: true },
             ],
         },
+        CommandSchema {
+            name: "split",
+            aliases: vec![],
+            description: "Split a large file into chunks with a SHA-256 manifest",
+            args: vec![ArgSchema { name: "file", description: "File to split", required: true, default: None, multiple: false }],
+            flags: vec![
+                FlagSchema { short: Some('s'), long: "size", description: "Maximum chunk size, e.g. 100MB", takes_value: true },
+                FlagSchema { short: None, long: "to", description: "Folder for chunks and manifest", takes_value: true },
+            ],
+        },
+        CommandSchema {
+            name: "join",
+            aliases: vec![],
+            description: "Reassemble a split file, verifying every chunk",
+            args: vec![ArgSchema { name: "manifest", description: "Manifest written by split", required: true, default: None, multiple: false }],
+            flags: vec![
+                FlagSchema { short: Some('o'), long: "output", description: "Where to write the joined file", takes_value: true },
+            ],
+        },
         CommandSchema {
             name: "space",
             aliases: vec!["diskfree"],
@@ -283,6 +466,21 @@ fn build_registry() -> Vec<CommandSchema> {
             args: vec![],
             flags: vec![],
         },
+        CommandSchema {
+            name: "normalize",
+            aliases: vec![],
+            description: "Convert files to UTF-8 with consistent line endings, no BOM and no trailing whitespace",
+            args: vec![ArgSchema { name: "paths", description: "Files, folders or globs", required: true, default: None, multiple: true }],
+            flags: vec![
+                FlagSchema { short: None, long: "check", description: "Only list files that need normalizing; exits with code 6 if any do", takes_value: false },
+                FlagSchema { short: None, long: "eol", description: "Line endings: lf, crlf or keep", takes_value: true },
+                FlagSchema { short: None, long: "keep-bom", description: "Keep a UTF-8 byte order mark", takes_value: false },
+                FlagSchema { short: None, long: "keep-whitespace", description: "Leave trailing whitespace alone", takes_value: false },
+                FlagSchema { short: None, long: "from", description: "Encoding of non-UTF-8 files (default windows-1252)", takes_value: true },
+                FlagSchema { short: None, long: "no-ignore", description: "Include files ignored by .gitignore", takes_value: false },
+                FlagSchema { short: None, long: "backup", description: "Keep a copy of each original (--backup[=SUFFIX])", takes_value: true },
+            ],
+        },
         CommandSchema {
             name: "count",
             aliases: vec![],
@@ -303,6 +501,8 @@ fn build_registry() -> Vec<CommandSchema> {
                 FlagSchema { short: Some('r'), long: "reverse", description: "Sort in reverse order", takes_value: false },
                 FlagSchema { short: Some('n'), long: "numeric", description: "Sort numerically", takes_value: false },
                 FlagSchema { short: Some('u'), long: "unique", description: "Remove duplicate lines", takes_value: false },
+                FlagSchema { short: Some('i'), long: "in-place", description: "Write the sorted lines back to the file", takes_value: false },
+                FlagSchema { short: None, long: "backup", description: "Keep a copy of the original (optional =SUFFIX, default .bak)", takes_value: true },
             ],
         },
         CommandSchema {
@@ -317,13 +517,37 @@ fn build_registry() -> Vec<CommandSchema> {
                 FlagSchema { short: Some('s'), long: "side-by-side", description: "Show side by side", takes_value: false },
             ],
         },
+        CommandSchema {
+            name: "link",
+            aliases: vec!["ln"],
+            description: "Create a symlink or hard link",
+            args: vec![
+                ArgSchema { name: "target", description: "What the link points to", required: true, default: None, multiple: false },
+                ArgSchema { name: "name", description: "Where to create the link", required: true, default: None, multiple: false },
+            ],
+            flags: vec![
+                FlagSchema { short: None, long: "hard", description: "Make a hard link instead of a symlink", takes_value: false },
+            ],
+        },
+        CommandSchema {
+            name: "link check",
+            aliases: vec!["ln check"],
+            description: "Find broken symlinks, loops and links leaving the folder",
+            args: vec![ArgSchema { name: "path", description: "Folder to check", required: false, default: Some("."), multiple: false }],
+            flags: vec![
+                FlagSchema { short: None, long: "fix", description: "Repoint links where the target can be worked out", takes_value: false },
+                FlagSchema { short: None, long: "remove", description: "Delete broken and looping links", takes_value: false },
+            ],
+        },
         CommandSchema {
             name: "make-runnable",
             aliases: vec![],
-            description: "Make file executable",
-            args: vec![ArgSchema { name: "file", description: "File to make executable", required: true, default: None, multiple: false }],
+            description: "Make file executable, checking its shebang line",
+            args: vec![ArgSchema { name: "file", description: "File to make executable (or folder with --all)", required: true, default: None, multiple: false }],
             flags: vec![
                 FlagSchema { short: None, long: "if-not-exists", description: "Skip if already executable", takes_value: false },
+                FlagSchema { short: None, long: "add-shebang", description: "Add a missing shebang guessed from the extension", takes_value: false },
+                FlagSchema { short: None, long: "all", description: "Make every script in the folder runnable", takes_value: false },
             ],
         },
         CommandSchema {
@@ -1,85 +1,534 @@
 use std::fs;
-use std::path::PathBuf;
+use std::collections::VecDeque;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use colored::*;
-use walkdir::WalkDir;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::{Regex, RegexBuilder};
+use crate::commands::index::{self, Index};
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
+use crate::scan::{Container, LineReader};
+
+/// How the pattern is matched and what gets reported.
+pub struct SearchOptions {
+    pub ignore_case: bool,
+    pub smart_case: bool,
+    pub word: bool,
+    pub invert: bool,
+    /// Treat the pattern as a regular expression instead of plain text
+    pub regex: bool,
+    pub multiline: bool,
+    pub files_only: bool,
+    pub count: bool,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Look inside .gz/.bz2 files and .zip/.tar(.gz/.bz2) archives
+    pub archives: bool,
+    /// Narrow the files read with the `ez index` trigram index
+    pub indexed: bool,
+}
+
+/// A matching line (or, with --multiline, the block of lines a match spans).
+struct LineMatch {
+    line: usize,
+    /// Byte offset of the line's start within the file
+    offset: u64,
+    text: String,
+    /// Byte ranges of each match within `text`
+    spans: Vec<(usize, usize)>,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+/// What searching one file turned up.
+struct FileScan {
+    /// Lines to show; left empty for binary files
+    matches: Vec<LineMatch>,
+    /// Matching lines, also counted for binary files
+    count: usize,
+    binary: bool,
+    lossy: bool,
+}
 
 pub fn execute(
     pattern: String,
     path: PathBuf,
     context: usize,
+    options: SearchOptions,
+    threads: usize,
     ctx: &CommandContext,
 ) -> Result<CommandOutput, EzError> {
+    let re = build_regex(&pattern, &options)?;
+    let include = glob_set(&options.include)?;
+    let exclude = glob_set(&options.exclude)?;
+    // Context lines only matter when lines are printed
+    let context = if options.files_only || options.count || ctx.json { 0 } else { context };
+
     let mut total_matches = 0;
     let mut files_with_matches = 0;
     let mut results = Vec::new();
 
-    for entry in WalkDir::new(&path).into_iter().filter_map(|e| e.ok()) {
-        if !entry.file_type().is_file() {
-            continue;
+    let search_file = |file: &Path| {
+        let relative = file.strip_prefix(&path).unwrap_or(file);
+        let included = |p: &Path| include.as_ref().map_or(true, |set| set.is_match(p));
+        let excluded = |p: &Path| exclude.as_ref().is_some_and(|set| p.ancestors().any(|a| set.is_match(a)));
+        if excluded(relative) {
+            return None;
+        }
+
+        let mut found = Vec::new();
+        match Container::detect(file).filter(|_| options.archives) {
+            None => {
+                if !included(relative) {
+                    return None;
+                }
+                let scanned = fs::File::open(file).and_then(|f| scan(f, &re, &options, context)).ok()?;
+                found.push((None, scanned));
+            }
+            Some(container) => {
+                // Globs apply to the files inside an archive; a plain app.log.gz also answers to app.log
+                let archive_included = container.is_archive() || included(relative) || included(&relative.with_extension(""));
+                // A corrupt archive keeps whatever was found before the damage
+                let _ = container.for_each_file(file, |inner, reader| {
+                    if let Some(name) = inner {
+                        if !included(Path::new(name)) || excluded(Path::new(name)) {
+                            return Ok(());
+                        }
+                    } else if !archive_included {
+                        return Ok(());
+                    }
+                    found.push((inner.map(String::from), scan(reader, &re, &options, context)?));
+                    Ok(())
+                });
+            }
         }
+        found.retain(|(_, scanned)| scanned.count > 0);
+        (!found.is_empty()).then_some(found)
+    };
 
-        let file_path = entry.path();
-        let contents = match fs::read_to_string(file_path) {
-            Ok(c) => c,
-            Err(_) => continue,
+    let (candidates, index_info) = if options.indexed {
+        indexed_candidates(&pattern, &path, &options, threads, ctx)
+    } else {
+        (None, None)
+    };
+    // Files are read on worker threads and reported in path order
+    let files = match candidates {
+        Some(candidates) => crate::walk::map_paths(candidates, threads, search_file),
+        None => {
+            crate::walk::walk(&path, &index::walk_options(threads), |entry| {
+                if !entry.file_type().is_some_and(|t| t.is_file()) || index::is_index_file(entry.file_name()) {
+                    return None;
+                }
+                search_file(entry.path())
+            })
+        }
+    };
+
+    for (file_path, (inner, scanned)) in files.into_iter().flat_map(|(p, found)| found.into_iter().map(move |f| (p.clone(), f))) {
+        files_with_matches += 1;
+        total_matches += scanned.count;
+        // Matches inside archives read as archive.tar.gz!inner/path.log
+        let file_str = match &inner {
+            Some(name) => format!("{}!{}", file_path.display(), name),
+            None => file_path.display().to_string(),
         };
 
-        let lines: Vec<&str> = contents.lines().collect();
-        let mut matches: Vec<usize> = Vec::new();
+        let mut entry = serde_json::json!({ "file": file_str, "count": scanned.count });
+        if let Some(name) = &inner {
+            entry["archive"] = serde_json::json!(file_path.display().to_string());
+            entry["inner"] = serde_json::json!(name);
+        }
+        if scanned.binary {
+            entry["binary"] = serde_json::json!(true);
+        }
+        if scanned.lossy {
+            entry["lossy"] = serde_json::json!(true);
+        }
 
-        for (idx, line) in lines.iter().enumerate() {
-            if line.contains(&pattern) {
-                matches.push(idx);
-                total_matches += 1;
+        if options.files_only {
+            if !ctx.json {
+                println!("{}", file_str);
             }
+            results.push(entry);
+            continue;
+        }
+        if options.count {
+            if !ctx.json {
+                println!("{}:{}", file_str, scanned.count.to_string().green());
+            }
+            results.push(entry);
+            continue;
         }
 
-        if !matches.is_empty() {
-            files_with_matches += 1;
-            let file_str = file_path.display().to_string();
+        if !ctx.json {
+            if scanned.binary {
+                println!("\n{} {} {}", "📁".cyan(), file_str.bold(), "(binary file matches)".dimmed());
+            } else {
+                print_file(&file_str, &scanned.matches, context);
+            }
+        }
 
-            let json_matches: Vec<_> = matches.iter().map(|&idx| {
-                serde_json::json!({
-                    "line": idx + 1,
-                    "text": lines[idx],
-                })
-            }).collect();
+        let json_matches: Vec<_> = scanned.matches.iter().map(|m| {
+            let mut entry = serde_json::json!({
+                "line": m.line,
+                "text": m.text,
+                "byte_offset": m.offset + m.spans.first().map_or(0, |s| s.0 as u64),
+            });
+            if let Some(&(start, _)) = m.spans.first() {
+                entry["column"] = serde_json::json!(column(&m.text, start));
+                entry["submatches"] = serde_json::json!(m.spans.iter().map(|&(start, end)| serde_json::json!({
+                    "text": &m.text[start..end],
+                    "column": column(&m.text, start),
+                    "byte_offset": m.offset + start as u64,
+                })).collect::<Vec<_>>());
+            }
+            entry
+        }).collect();
 
-            results.push(serde_json::json!({
-                "file": file_str,
-                "matches": json_matches,
-            }));
+        entry["matches"] = serde_json::json!(json_matches);
+        results.push(entry);
+    }
 
-            if !ctx.json {
-                println!("\n{} {}", "📁".cyan(), file_str.bold());
+    if !ctx.json && !options.files_only && (!options.count || ctx.is_tty) {
+        let what = if options.invert { "non-matching lines" } else { "matches" };
+        println!("\n{} Found {} {} in {} files", "✓".green(), total_matches, what, files_with_matches);
+    }
 
-                for &match_idx in &matches {
-                    let start = match_idx.saturating_sub(context);
-                    for i in start..match_idx {
-                        println!("  {} {}", (i + 1).to_string().dimmed(), lines[i].dimmed());
-                    }
+    let mut metadata = serde_json::json!({
+        "total_matches": total_matches,
+        "total_files": files_with_matches,
+        "pattern": pattern,
+        "regex": re.as_str(),
+        "invert": options.invert,
+        "multiline": options.multiline,
+    });
+    if let Some(info) = index_info {
+        metadata["index"] = info;
+    }
+    Ok(CommandOutput::new("search", serde_json::json!(results)).with_metadata(metadata))
+}
 
-                    let highlighted = lines[match_idx].replace(&pattern, &pattern.yellow().to_string());
-                    println!("  {} {}", (match_idx + 1).to_string().green().bold(), highlighted);
+/// Files the trigram index says could match, as paths under `path`. `None`
+/// means read everything: there's no index, or files changed since it was built.
+fn indexed_candidates(
+    pattern: &str,
+    path: &Path,
+    options: &SearchOptions,
+    threads: usize,
+    ctx: &CommandContext,
+) -> (Option<Vec<PathBuf>>, Option<serde_json::Value>) {
+    let Some(index) = Index::find_root(path).and_then(|root| Index::load(&root).ok()) else {
+        if !ctx.json {
+            println!("{} {}", "⚠️".yellow(),
+                format!("No index found for '{}'; searching every file. Run `ez index build` to create one", path.display()).yellow());
+        }
+        return (None, Some(serde_json::json!({ "used": false, "reason": "missing" })));
+    };
+    let stale = index.stale_count(threads);
+    if stale > 0 {
+        if !ctx.json {
+            println!("{} {}", "⚠️".yellow(),
+                format!("Index is out of date ({} change(s)); searching every file. Run `ez index build` to refresh it", stale).yellow());
+        }
+        return (None, Some(serde_json::json!({
+            "used": false,
+            "reason": "stale",
+            "root": index.root.display().to_string(),
+            "stale": stale,
+        })));
+    }
 
-                    let end = (match_idx + context + 1).min(lines.len());
-                    for i in (match_idx + 1)..end {
-                        println!("  {} {}", (i + 1).to_string().dimmed(), lines[i].dimmed());
-                    }
+    // Inverted searches match lines without the pattern, so nothing can be ruled out
+    let literals = if options.invert {
+        Vec::new()
+    } else if options.regex {
+        index::required_literals(pattern)
+    } else {
+        vec![pattern.as_bytes().to_vec()]
+    };
+    let mut files = index.candidates(&literals, is_case_insensitive(pattern, options));
+    // Compressed bytes say nothing about what's inside, so containers are always read
+    if options.archives {
+        files.extend(index.files().filter(|f| Container::detect(f).is_some()));
+        files.sort();
+        files.dedup();
+    }
+
+    // The index may cover a bigger folder than the one being searched
+    let prefix = fs::canonicalize(path).ok()
+        .and_then(|p| p.strip_prefix(&index.root).ok().map(Path::to_path_buf))
+        .unwrap_or_default();
+    let paths: Vec<PathBuf> = files.iter()
+        .filter_map(|f| f.strip_prefix(&prefix).ok())
+        .map(|rel| if rel.as_os_str().is_empty() { path.to_path_buf() } else { path.join(rel) })
+        .collect();
+    let info = serde_json::json!({
+        "used": true,
+        "root": index.root.display().to_string(),
+        "files": index.file_count(),
+        "candidates": paths.len(),
+    });
+    (Some(paths), Some(info))
+}
+
+fn is_case_insensitive(pattern: &str, options: &SearchOptions) -> bool {
+    options.ignore_case || (options.smart_case && !pattern.chars().any(char::is_uppercase))
+}
+
+/// Turn the pattern and flags into one regex. Smart case searches
+/// case-insensitively unless the pattern has an uppercase letter.
+fn build_regex(pattern: &str, options: &SearchOptions) -> Result<Regex, EzError> {
+    let mut source = if options.regex { pattern.to_string() } else { regex::escape(pattern) };
+    if options.word {
+        source = format!(r"\b(?:{})\b", source);
+    }
+    RegexBuilder::new(&source)
+        .case_insensitive(is_case_insensitive(pattern, options))
+        .multi_line(options.multiline)
+        .build()
+        .map_err(|e| EzError::InvalidArgs(format!(
+            "Invalid pattern '{}': {}\nDrop --regex to search for the text as-is", pattern, e
+        )))
+}
+
+fn glob_set(patterns: &[String]) -> Result<Option<GlobSet>, EzError> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|e| EzError::InvalidArgs(format!("Invalid glob '{}': {}", pattern, e)))?);
+    }
+    builder.build().map(Some).map_err(|e| EzError::InvalidArgs(format!("Invalid glob: {}", e)))
+}
+
+/// First line index, last line index, spans relative to the first line's start.
+type Hit = (usize, usize, Vec<(usize, usize)>);
+
+/// Multiline patterns need the whole file; everything else streams line by line.
+fn scan<R: Read>(mut reader: R, re: &Regex, options: &SearchOptions, context: usize) -> std::io::Result<FileScan> {
+    if options.multiline {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Ok(scan_whole(&bytes, re, options, context))
+    } else {
+        scan_lines(reader, re, options, context)
+    }
+}
+
+/// Read line by line, keeping only `context` earlier lines around.
+/// Binary files are only counted, and stop at the first match unless --count
+/// needs the total.
+fn scan_lines<R: Read>(reader: R, re: &Regex, options: &SearchOptions, context: usize) -> std::io::Result<FileScan> {
+    let mut reader = LineReader::new(reader)?;
+    let mut matches: Vec<LineMatch> = Vec::new();
+    let mut before: VecDeque<String> = VecDeque::with_capacity(context);
+    let mut count = 0;
+
+    while let Some(line) = reader.next_line()? {
+        let spans: Vec<(usize, usize)> = re.find_iter(&line.text).map(|m| (m.start(), m.end())).collect();
+        let hit = spans.is_empty() == options.invert;
+        let (number, offset) = (line.number, line.offset);
+        let text = (hit || context > 0).then(|| line.text.into_owned());
+
+        if reader.binary {
+            if hit {
+                count += 1;
+                if !options.count {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if let Some(text) = text {
+            // Earlier matches still short of their trailing context take this line
+            for m in matches.iter_mut().rev().take(context) {
+                if m.after.len() < context {
+                    m.after.push(text.clone());
                 }
             }
+            if hit {
+                count += 1;
+                matches.push(LineMatch {
+                    line: number,
+                    offset,
+                    text: text.clone(),
+                    spans: if options.invert { Vec::new() } else { spans },
+                    before: before.iter().cloned().collect(),
+                    after: Vec::new(),
+                });
+            }
+            if context > 0 {
+                if before.len() == context {
+                    before.pop_front();
+                }
+                before.push_back(text);
+            }
+        }
+    }
+
+    if reader.binary {
+        matches.clear();
+    }
+    Ok(FileScan { matches, count, binary: reader.binary, lossy: reader.lossy })
+}
+
+/// Search a whole file at once so a match can span lines.
+fn scan_whole(bytes: &[u8], re: &Regex, options: &SearchOptions, context: usize) -> FileScan {
+    let binary = bytes.contains(&0);
+    let contents = String::from_utf8_lossy(bytes);
+    let mut matches = scan_text(&contents, re, options, context);
+    let count = matches.len();
+    if binary {
+        matches.clear();
+    }
+    FileScan { matches, count, binary, lossy: matches!(contents, std::borrow::Cow::Owned(_)) }
+}
+
+/// Find the blocks of lines that multiline matches span, with `context` lines around each.
+fn scan_text(contents: &str, re: &Regex, options: &SearchOptions, context: usize) -> Vec<LineMatch> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for raw in contents.split_inclusive('\n') {
+        lines.push((offset, raw.trim_end_matches(['\n', '\r'])));
+        offset += raw.len();
+    }
+    if lines.is_empty() {
+        return Vec::new();
+    }
+
+    let mut hits: Vec<Hit> = Vec::new();
+    for m in re.find_iter(contents) {
+        let first = lines.partition_point(|(start, _)| *start <= m.start()).saturating_sub(1);
+        let last = lines.partition_point(|(start, _)| *start < m.end().max(m.start() + 1)).saturating_sub(1);
+        // A match starting inside the previous block joins it
+        match hits.last_mut() {
+            Some(hit) if first <= hit.1 => {
+                let base = lines[hit.0].0;
+                hit.1 = hit.1.max(last);
+                hit.2.push((m.start() - base, m.end() - base));
+            }
+            _ => {
+                let base = lines[first].0;
+                hits.push((first, last, vec![(m.start() - base, m.end() - base)]));
+            }
+        }
+    }
+
+    if options.invert {
+        let mut covered = vec![false; lines.len()];
+        for (first, last, _) in &hits {
+            for flag in covered.iter_mut().take(last + 1).skip(*first) {
+                *flag = true;
+            }
         }
+        hits = (0..lines.len()).filter(|&i| !covered[i]).map(|i| (i, i, Vec::new())).collect();
     }
 
-    if !ctx.json {
-        println!("\n{} Found {} matches in {} files", "✓".green(), total_matches, files_with_matches);
+    hits.into_iter().map(|(first, last, spans)| {
+        let start = lines[first].0;
+        let end = lines[last].0 + lines[last].1.len();
+        let text = contents[start..end].to_string();
+        let spans = spans.into_iter().map(|(s, e)| (s.min(text.len()), e.min(text.len()))).collect();
+        LineMatch {
+            line: first + 1,
+            offset: start as u64,
+            text,
+            spans,
+            before: lines[first.saturating_sub(context)..first].iter().map(|l| l.1.to_string()).collect(),
+            after: lines[last + 1..(last + 1 + context).min(lines.len())].iter().map(|l| l.1.to_string()).collect(),
+        }
+    }).collect()
+}
+
+/// 1-based column of a byte position, counted in characters.
+fn column(text: &str, byte: usize) -> usize {
+    let line_start = text[..byte].rfind('\n').map_or(0, |i| i + 1);
+    text[line_start..byte].chars().count() + 1
+}
+
+/// Print matches with their context like grep: lines shared by neighbouring
+/// matches are shown once, and `--` separates blocks that aren't adjacent.
+fn print_file(file: &str, matches: &[LineMatch], context: usize) {
+    println!("\n{} {}", "📁".cyan(), file.bold());
+    // Last line number printed so far
+    let mut printed = 0;
+    for (i, m) in matches.iter().enumerate() {
+        let first = m.line - m.before.len();
+        let start = first.max(printed + 1);
+        if context > 0 && printed > 0 && start > printed + 1 {
+            println!("  {}", "--".dimmed());
+        }
+        for (n, line) in (first..).zip(&m.before).filter(|(n, _)| *n >= start) {
+            println!("  {} {}", n.to_string().dimmed(), line.dimmed());
+        }
+
+        let highlighted = highlight(&m.text, &m.spans);
+        for (i, line) in highlighted.split('\n').enumerate() {
+            println!("  {} {}", (m.line + i).to_string().green().bold(), line.trim_end_matches('\r'));
+        }
+        let next = m.line + m.text.matches('\n').count() + 1;
+        printed = next - 1;
+
+        // Trailing context stops where the next match (or its leading context) takes over
+        let limit = matches.get(i + 1).map_or(usize::MAX, |n| n.line);
+        for (n, line) in (next..).zip(&m.after).take_while(|(n, _)| *n < limit) {
+            println!("  {} {}", n.to_string().dimmed(), line.dimmed());
+            printed = n;
+        }
     }
+}
+
+/// Color each span, line by line so a match spanning lines stays readable.
+fn highlight(text: &str, spans: &[(usize, usize)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+    for &(start, end) in spans {
+        if start < pos {
+            continue;
+        }
+        out.push_str(&text[pos..start]);
+        let parts: Vec<String> = text[start..end].split('\n').map(|p| p.yellow().to_string()).collect();
+        out.push_str(&parts.join("\n"));
+        pos = end;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(CommandOutput::new("search", serde_json::json!(results))
-        .with_metadata(serde_json::json!({
-            "total_matches": total_matches,
-            "total_files": files_with_matches,
-        })))
+    fn options(regex: bool) -> SearchOptions {
+        SearchOptions {
+            ignore_case: false, smart_case: false, word: false, invert: false, regex, multiline: false,
+            files_only: false, count: false, include: Vec::new(), exclude: Vec::new(), archives: false, indexed: false,
+        }
+    }
+
+    #[test]
+    fn patterns_are_plain_text_unless_regex() {
+        let literal = build_regex("a.c(", &options(false)).ok().unwrap();
+        assert!(literal.is_match("x a.c( y"));
+        assert!(!literal.is_match("abc("));
+        let regex = build_regex("a.c", &options(true)).ok().unwrap();
+        assert!(regex.is_match("abc"));
+        assert!(build_regex("a.c(", &options(true)).is_err());
+    }
+
+    #[test]
+    fn context_lines_follow_each_match() {
+        let text = "one\ntwo hit\nthree\nfour hit\nfive\n";
+        let re = build_regex("hit", &options(false)).ok().unwrap();
+        let scan = scan_lines(text.as_bytes(), &re, &options(false), 1).unwrap();
+        assert_eq!(scan.count, 2);
+        let lines: Vec<usize> = scan.matches.iter().map(|m| m.line).collect();
+        assert_eq!(lines, [2, 4]);
+        assert_eq!(scan.matches[0].before, ["one"]);
+        assert_eq!(scan.matches[0].after, ["three"]);
+        assert_eq!(scan.matches[1].after, ["five"]);
+    }
 }
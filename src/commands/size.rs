@@ -1,11 +1,12 @@
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use colored::*;
-use walkdir::WalkDir;
+use crate::walk::WalkOptions;
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
 
-pub fn execute(path: PathBuf, detailed: bool, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+pub fn execute(path: PathBuf, detailed: bool, threads: usize, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
     if !path.exists() {
         return Err(EzError::NotFound(format!("Path '{}' does not exist", path.display())));
     }
@@ -24,25 +25,27 @@ pub fn execute(path: PathBuf, detailed: bool, ctx: &CommandContext) -> Result<Co
     }
 
     if detailed {
-        show_detailed(&path, ctx)
+        show_detailed(&path, threads, ctx)
     } else {
-        show_total(&path, ctx)
+        show_total(&path, threads, ctx)
     }
 }
 
-fn show_total(path: &PathBuf, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+fn show_total(path: &Path, threads: usize, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    let options = WalkOptions { threads, ..Default::default() };
+    let entries = crate::walk::walk(path, &options, |entry| {
+        (entry.depth() > 0).then(|| entry.metadata().ok()).flatten()
+    });
+
     let mut total_size: u64 = 0;
     let mut file_count: u64 = 0;
     let mut dir_count: u64 = 0;
-
-    for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
-        if let Ok(metadata) = entry.metadata() {
-            if metadata.is_file() {
-                total_size += metadata.len();
-                file_count += 1;
-            } else if metadata.is_dir() && entry.path() != path.as_path() {
-                dir_count += 1;
-            }
+    for (_, metadata) in entries {
+        if metadata.is_file() {
+            total_size += metadata.len();
+            file_count += 1;
+        } else if metadata.is_dir() {
+            dir_count += 1;
         }
     }
 
@@ -59,32 +62,41 @@ fn show_total(path: &PathBuf, ctx: &CommandContext) -> Result<CommandOutput, EzE
     })))
 }
 
-fn show_detailed(path: &PathBuf, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
-    let mut entries_data: Vec<serde_json::Value> = vec![];
+fn show_detailed(path: &Path, threads: usize, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    fs::read_dir(path).map_err(|e| EzError::General(format!("Cannot read directory: {}", e)))?;
 
-    for entry in fs::read_dir(path).map_err(|e| EzError::General(format!("Cannot read directory: {}", e)))? {
-        let entry = entry.map_err(|e| EzError::General(format!("Read error: {}", e)))?;
-        let entry_path = entry.path();
-        let name = entry.file_name().to_string_lossy().to_string();
+    // One walk for the whole tree; every file's size is credited to the top-level entry it sits under
+    let options = WalkOptions { threads, ..Default::default() };
+    let entries = crate::walk::walk(path, &options, |entry| {
+        (entry.depth() > 0).then(|| entry.metadata().ok().map(|m| (entry.depth(), m))).flatten()
+    });
 
+    let mut top: Vec<(String, bool, u64)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for (entry_path, (depth, metadata)) in entries {
+        let Some(first) = entry_path.strip_prefix(path).ok().and_then(|r| r.components().next()) else { continue };
+        let name = first.as_os_str().to_string_lossy().to_string();
         if name.starts_with('.') {
             continue;
         }
+        if depth == 1 {
+            let is_dir = metadata.is_dir();
+            index.insert(name.clone(), top.len());
+            top.push((name, is_dir, if is_dir { 0 } else { metadata.len() }));
+        } else if metadata.is_file() {
+            if let Some(&i) = index.get(&name) {
+                top[i].2 += metadata.len();
+            }
+        }
+    }
 
-        let metadata = entry.metadata();
-        let is_dir = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
-        let size = if is_dir {
-            calculate_dir_size(&entry_path)
-        } else {
-            metadata.map(|m| m.len()).unwrap_or(0)
-        };
-
-        entries_data.push(serde_json::json!({
+    let mut entries_data: Vec<serde_json::Value> = top.into_iter()
+        .map(|(name, is_dir, size)| serde_json::json!({
             "name": name,
             "type": if is_dir { "directory" } else { "file" },
             "size": size,
-        }));
-    }
+        }))
+        .collect();
 
     entries_data.sort_by(|a, b| {
         let a_size = a["size"].as_u64().unwrap_or(0);
@@ -107,15 +119,3 @@ fn show_detailed(path: &PathBuf, ctx: &CommandContext) -> Result<CommandOutput,
 
     Ok(CommandOutput::new("size", serde_json::json!(entries_data)))
 }
-
-fn calculate_dir_size(path: &PathBuf) -> u64 {
-    let mut total: u64 = 0;
-    for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
-        if let Ok(metadata) = entry.metadata() {
-            if metadata.is_file() {
-                total += metadata.len();
-            }
-        }
-    }
-    total
-}
@@ -1,9 +1,22 @@
 use std::fs;
 use std::path::PathBuf;
+use colored::*;
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
 
-pub fn execute(file: PathBuf, reverse: bool, numeric: bool, unique: bool, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+pub fn execute(
+    file: PathBuf,
+    reverse: bool,
+    numeric: bool,
+    unique: bool,
+    in_place: bool,
+    backup: Option<String>,
+    ctx: &CommandContext,
+) -> Result<CommandOutput, EzError> {
+    if backup.is_some() && !in_place {
+        return Err(EzError::InvalidArgs("--backup only applies to --in-place; sorted output goes to the terminal otherwise".to_string()));
+    }
+
     let contents = fs::read_to_string(&file).map_err(|e| {
         if e.kind() == std::io::ErrorKind::NotFound {
             EzError::NotFound(format!("Cannot read '{}': {}", file.display(), e))
@@ -32,6 +45,10 @@ pub fn execute(file: PathBuf, reverse: bool, numeric: bool, unique: bool, ctx: &
         lines.reverse();
     }
 
+    if in_place {
+        return write_in_place(file, lines, backup, ctx);
+    }
+
     if !ctx.json {
         for line in &lines {
             println!("{}", line);
@@ -43,3 +60,37 @@ pub fn execute(file: PathBuf, reverse: bool, numeric: bool, unique: bool, ctx: &
         "lines": lines,
     })))
 }
+
+fn write_in_place(file: PathBuf, lines: Vec<String>, backup: Option<String>, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    if ctx.dry_run {
+        if !ctx.json {
+            println!("{} Would sort {} line(s) in {}", "~".dimmed(), lines.len(), file.display().to_string().cyan());
+        }
+        return Ok(CommandOutput::new("sort", serde_json::json!({
+            "file": file.display().to_string(),
+            "lines": lines,
+        })).with_metadata(serde_json::json!({ "dry_run": true, "in_place": true })));
+    }
+
+    let mut contents = lines.join("\n");
+    if !lines.is_empty() {
+        contents.push('\n');
+    }
+    let backup_path = crate::utils::write_atomic(&file, contents.as_bytes(), backup.as_deref()).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            EzError::PermissionDenied(format!("Cannot write to '{}': {}", file.display(), e))
+        } else {
+            EzError::General(format!("Cannot write to '{}': {}", file.display(), e))
+        }
+    })?;
+
+    if !ctx.json {
+        println!("{} Sorted {} line(s) in {}", "✓".green(), lines.len(), file.display().to_string().cyan());
+    }
+
+    Ok(CommandOutput::new("sort", serde_json::json!({
+        "file": file.display().to_string(),
+        "lines": lines.len(),
+        "backup": backup_path.map(|b| b.display().to_string()),
+    })).with_metadata(serde_json::json!({ "in_place": true })))
+}
@@ -0,0 +1,148 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::context::CommandContext;
+use crate::output::{CommandOutput, EzError};
+
+/// Written next to the chunks as `<file>.manifest.json`; read back by `ez join`.
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub file: String,
+    pub size: u64,
+    pub sha256: String,
+    pub chunk_size: u64,
+    pub chunks: Vec<Chunk>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Chunk {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+pub fn execute(file: PathBuf, size: String, to: Option<PathBuf>, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    let chunk_size = crate::utils::parse_size(&size)
+        .filter(|s| *s > 0)
+        .ok_or_else(|| EzError::InvalidArgs(format!("Invalid size '{}'. Use e.g. 100MB, 1.5G or 500K", size)))?;
+
+    let metadata = fs::metadata(&file).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            EzError::NotFound(format!("Cannot access '{}': {}", file.display(), e))
+        } else {
+            EzError::General(format!("Cannot access '{}': {}", file.display(), e))
+        }
+    })?;
+    if !metadata.is_file() {
+        return Err(EzError::InvalidArgs(format!("'{}' is not a file", file.display())));
+    }
+
+    let total = metadata.len();
+    let file_name = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let out_dir = to.unwrap_or_else(|| file.parent().unwrap_or(Path::new("")).to_path_buf());
+    let count = (total / chunk_size + u64::from(total % chunk_size != 0)).max(1);
+    let width = count.to_string().len().max(3);
+    let chunk_names: Vec<String> = (1..=count).map(|i| format!("{}.{:0width$}", file_name, i, width = width)).collect();
+    let manifest_path = out_dir.join(format!("{}.manifest.json", file_name));
+
+    // Refuse to overwrite earlier pieces; a mix of old and new chunks is worse than an error
+    for name in chunk_names.iter().map(|n| out_dir.join(n)).chain(std::iter::once(manifest_path.clone())) {
+        if name.exists() {
+            return Err(EzError::InvalidArgs(format!(
+                "'{}' already exists. Remove old pieces or pick another folder with --to", name.display()
+            )));
+        }
+    }
+
+    if ctx.dry_run {
+        if !ctx.json {
+            println!("{} Would split '{}' ({}) into {} chunk(s) of up to {}:",
+                "~".dimmed(), file.display(), crate::utils::format_size(total), count, crate::utils::format_size(chunk_size));
+            for name in &chunk_names {
+                println!("  {}", out_dir.join(name).display());
+            }
+            println!("  {}", manifest_path.display());
+        }
+        return Ok(CommandOutput::new("split", serde_json::json!({
+            "file": file.display().to_string(),
+            "chunks": chunk_names,
+            "manifest": manifest_path.display().to_string(),
+        })).with_metadata(serde_json::json!({ "dry_run": true, "size": total, "chunk_size": chunk_size })));
+    }
+
+    if !out_dir.as_os_str().is_empty() {
+        fs::create_dir_all(&out_dir)
+            .map_err(|e| EzError::General(format!("Cannot create folder '{}': {}", out_dir.display(), e)))?;
+    }
+
+    let mut input = fs::File::open(&file)
+        .map_err(|e| EzError::PermissionDenied(format!("Cannot read '{}': {}", file.display(), e)))?;
+    let pb = if ctx.json { None } else { Some(crate::utils::byte_progress_bar(total)) };
+
+    let mut whole = Sha256::new();
+    let mut chunks = Vec::new();
+    let mut buffer = vec![0; 65536];
+
+    for name in &chunk_names {
+        let path = out_dir.join(name);
+        let mut output = fs::File::create(&path)
+            .map_err(|e| EzError::General(format!("Cannot create '{}': {}", path.display(), e)))?;
+        let mut hasher = Sha256::new();
+        let mut written = 0u64;
+
+        while written < chunk_size {
+            let want = (chunk_size - written).min(buffer.len() as u64) as usize;
+            let n = input.read(&mut buffer[..want])
+                .map_err(|e| EzError::General(format!("Cannot read '{}': {}", file.display(), e)))?;
+            if n == 0 {
+                break;
+            }
+            output.write_all(&buffer[..n])
+                .map_err(|e| EzError::General(format!("Cannot write '{}': {}", path.display(), e)))?;
+            hasher.update(&buffer[..n]);
+            whole.update(&buffer[..n]);
+            written += n as u64;
+            if let Some(pb) = &pb {
+                pb.inc(n as u64);
+            }
+        }
+        output.sync_all()
+            .map_err(|e| EzError::General(format!("Cannot write '{}': {}", path.display(), e)))?;
+
+        chunks.push(Chunk { name: name.clone(), size: written, sha256: format!("{:x}", hasher.finalize()) });
+    }
+    if let Some(pb) = pb {
+        pb.finish_and_clear();
+    }
+
+    let manifest = Manifest {
+        version: 1,
+        file: file_name,
+        size: total,
+        sha256: format!("{:x}", whole.finalize()),
+        chunk_size,
+        chunks,
+    };
+    let json = serde_json::to_string_pretty(&manifest).unwrap();
+    fs::write(&manifest_path, json + "\n")
+        .map_err(|e| EzError::General(format!("Cannot write '{}': {}", manifest_path.display(), e)))?;
+
+    if !ctx.json {
+        println!("{} Split '{}' ({}) into {} chunk(s) in '{}'",
+            "✓".green(), file.display().to_string().cyan(), crate::utils::format_size(total),
+            manifest.chunks.len(), if out_dir.as_os_str().is_empty() { ".".to_string() } else { out_dir.display().to_string() });
+        println!("  Manifest: {}", manifest_path.display().to_string().cyan());
+        println!("  Rebuild with: {}", format!("ez join {}", manifest_path.display()).dimmed());
+    }
+
+    Ok(CommandOutput::new("split", serde_json::json!({
+        "file": file.display().to_string(),
+        "manifest": manifest_path.display().to_string(),
+        "sha256": manifest.sha256,
+        "chunks": manifest.chunks,
+    })).with_metadata(serde_json::json!({ "size": total, "chunk_size": chunk_size })))
+}
@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use colored::*;
+use regex::Regex;
+use crate::context::CommandContext;
+use crate::output::{CommandOutput, EzError};
+use crate::scan::LineReader;
+use crate::walk::WalkOptions;
+use crate::TodoGroup;
+
+pub const DEFAULT_TAGS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+
+/// One tagged comment, with the owner and issue numbers it mentions.
+struct Todo {
+    line: usize,
+    tag: String,
+    owner: Option<String>,
+    issues: Vec<u64>,
+    text: String,
+}
+
+/// A group's key (`None` for TODOs without an owner) and its items.
+type Group<'a> = (Option<String>, Vec<(&'a Path, &'a Todo)>);
+
+pub fn execute(path: PathBuf, tags: Vec<String>, group_by: TodoGroup, threads: usize, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    if !path.exists() {
+        return Err(EzError::NotFound(format!("Cannot access '{}': No such file or directory", path.display())));
+    }
+    let tags: Vec<String> = if tags.is_empty() {
+        DEFAULT_TAGS.iter().map(|t| t.to_string()).collect()
+    } else {
+        tags
+    };
+    if let Some(bad) = tags.iter().find(|t| t.trim().is_empty()) {
+        return Err(EzError::InvalidArgs(format!("Invalid tag '{}'", bad)));
+    }
+
+    // Tags are matched case-sensitively and must be followed by `:`, `(`, a space or the
+    // line end, so "todo" in prose and mentions like "TODO/FIXME" aren't picked up
+    let alternatives: Vec<String> = tags.iter().map(|t| regex::escape(t.trim())).collect();
+    let tag_re = Regex::new(&format!(r"\b({})\b(?:\(([^)]*)\))?(?:[:\s-]|$)", alternatives.join("|")))
+        .map_err(|e| EzError::InvalidArgs(format!("Invalid tag: {}", e)))?;
+    let issue_re = Regex::new(r"(?:^|[^\w&])#(\d+)\b").unwrap();
+
+    let options = WalkOptions { hidden: false, respect_ignore: true, skip_git: true, threads, ..Default::default() };
+    let files = crate::walk::walk(&path, &options, |entry| {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            return None;
+        }
+        let todos = scan_file(entry.path(), &tag_re, &issue_re).ok()?;
+        (!todos.is_empty()).then_some(todos)
+    });
+
+    let total: usize = files.iter().map(|(_, todos)| todos.len()).sum();
+    let mut by_tag: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut by_owner: BTreeMap<&str, usize> = BTreeMap::new();
+    // Files come out of the walk already sorted
+    let mut groups: Vec<Group> = Vec::new();
+    for (file, todos) in &files {
+        for todo in todos {
+            *by_tag.entry(&todo.tag).or_default() += 1;
+            if let Some(owner) = &todo.owner {
+                *by_owner.entry(owner).or_default() += 1;
+            }
+            let key = match group_by {
+                TodoGroup::File => Some(file.display().to_string()),
+                TodoGroup::Tag => Some(todo.tag.clone()),
+                TodoGroup::Owner => todo.owner.clone(),
+            };
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, items)) => items.push((file, todo)),
+                None => groups.push((key, vec![(file, todo)])),
+            }
+        }
+    }
+    if group_by != TodoGroup::File {
+        // Biggest groups first; unassigned items last when grouping by owner
+        groups.sort_by(|a, b| a.0.is_none().cmp(&b.0.is_none()).then(b.1.len().cmp(&a.1.len())).then(a.0.cmp(&b.0)));
+    }
+
+    if !ctx.json {
+        if total == 0 {
+            println!("{} No {} comments found in '{}'", "✓".green(), tags.join("/"), path.display());
+        }
+        for (key, items) in &groups {
+            let title = key.clone().unwrap_or_else(|| "(no owner)".to_string());
+            println!("\n{} {}", title.bold().cyan(), format!("({})", items.len()).dimmed());
+            for (file, todo) in items {
+                let location = match group_by {
+                    TodoGroup::File => format!("{:>6}", todo.line),
+                    _ => format!("{}:{}", file.display(), todo.line),
+                };
+                let tag = match todo.tag.as_str() {
+                    "FIXME" | "XXX" => todo.tag.red(),
+                    "HACK" => todo.tag.magenta(),
+                    _ => todo.tag.yellow(),
+                };
+                let owner = match (&todo.owner, group_by) {
+                    (Some(owner), TodoGroup::File | TodoGroup::Tag) => format!("({})", owner).dimmed().to_string(),
+                    _ => String::new(),
+                };
+                // Issues given as TODO(#123) aren't in the text itself
+                let refs: Vec<String> = todo.issues.iter().map(|i| format!("#{}", i)).filter(|r| !todo.text.contains(r.as_str())).collect();
+                let refs = if refs.is_empty() { String::new() } else { format!(" {}", refs.join(" ")).dimmed().to_string() };
+                println!("  {}  {}{} {}{}", location.dimmed(), tag.bold(), owner, todo.text, refs);
+            }
+        }
+        if total > 0 {
+            let counts: Vec<String> = by_tag.iter().map(|(tag, n)| format!("{} {}", n, tag)).collect();
+            println!("\n{} {} in {} file(s)", "✓".green(), counts.join(", "), files.len());
+        }
+    }
+
+    let data: Vec<_> = groups.iter().map(|(key, items)| serde_json::json!({
+        "group": key,
+        "count": items.len(),
+        "items": items.iter().map(|(file, todo)| serde_json::json!({
+            "file": file.display().to_string(),
+            "line": todo.line,
+            "tag": todo.tag,
+            "owner": todo.owner,
+            "issues": todo.issues,
+            "text": todo.text,
+        })).collect::<Vec<_>>(),
+    })).collect();
+
+    Ok(CommandOutput::new("todos", serde_json::json!(data))
+        .with_metadata(serde_json::json!({
+            "total": total,
+            "files": files.len(),
+            "group_by": format!("{:?}", group_by).to_lowercase(),
+            "tags": by_tag,
+            "owners": by_owner,
+            "unassigned": total - by_owner.values().sum::<usize>(),
+        })))
+}
+
+/// Tagged comments in one file, read line by line like `ez search` does.
+fn scan_file(path: &Path, tag_re: &Regex, issue_re: &Regex) -> std::io::Result<Vec<Todo>> {
+    let mut reader = LineReader::open(path)?;
+    let mut todos = Vec::new();
+    while let Some(line) = reader.next_line()? {
+        let number = line.number;
+        // The first tag inside a comment; `"TODO"` in a string doesn't count
+        let found = tag_re.captures_iter(&line.text).find(|caps| {
+            caps.get(0).is_some_and(|m| in_comment(&line.text[..m.start()]))
+        });
+        let Some(caps) = found else { continue };
+        let whole = caps.get(0).map_or(0..0, |m| m.range());
+        // Parentheses after the tag name an owner (alice, @alice) and/or issues (#123)
+        let mut owner = None;
+        let mut issues = Vec::new();
+        if let Some(inside) = caps.get(2).map(|m| m.as_str().trim()) {
+            for part in inside.split(',').map(str::trim) {
+                match part.strip_prefix('#').and_then(|n| n.parse().ok()) {
+                    Some(issue) => issues.push(issue),
+                    None if owner.is_none() && !part.is_empty() => owner = Some(part.trim_start_matches('@').to_string()),
+                    None => {}
+                }
+            }
+        }
+        let text = line.text[whole.end..]
+            .trim_start_matches([':', '-', ' ', '\t'])
+            .trim_end()
+            .trim_end_matches("*/")
+            .trim_end_matches("-->")
+            .trim_end()
+            .to_string();
+        for m in issue_re.captures_iter(&text) {
+            if let Ok(issue) = m[1].parse() {
+                if !issues.contains(&issue) {
+                    issues.push(issue);
+                }
+            }
+        }
+        todos.push(Todo { line: number, tag: caps[1].to_string(), owner, issues, text });
+    }
+    // A NUL byte means this was never source code
+    Ok(if reader.binary { Vec::new() } else { todos })
+}
+
+/// Whether a tag starts a comment in a common language: right after `//`,
+/// `#`, `/*`, `*` (inside a block comment), `--`, `;`, `%` or `<!--`.
+/// Tags mentioned later in a comment's prose don't count.
+fn in_comment(before: &str) -> bool {
+    let before = before.trim_end();
+    let code = before.trim_end_matches(['/', '*', '#', '-', ';', '%', '!', '<']);
+    let marker = &before[code.len()..];
+    ["//", "#", "/*", "--", ";", "%", "<!--"].iter().any(|m| marker.contains(m))
+        || (code.trim().is_empty() && marker.starts_with('*'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(content: &str) -> Vec<Todo> {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("source.rs");
+        std::fs::write(&file, content).unwrap();
+        let tag_re = Regex::new(r"\b(TODO|FIXME)\b(?:\(([^)]*)\))?(?:[:\s-]|$)").unwrap();
+        let issue_re = Regex::new(r"(?:^|[^\w&])#(\d+)\b").unwrap();
+        scan_file(&file, &tag_re, &issue_re).unwrap()
+    }
+
+    #[test]
+    fn tags_must_start_a_comment() {
+        assert!(in_comment("// "));
+        assert!(in_comment("    # "));
+        assert!(in_comment("let x = 1; /* "));
+        assert!(in_comment(" * "));
+        assert!(in_comment("<!-- "));
+        assert!(in_comment("-- "));
+        assert!(!in_comment(""));
+        assert!(!in_comment("let s = "));
+        assert!(!in_comment("// see the "));
+        assert!(!in_comment("a * "));
+    }
+
+    #[test]
+    fn owners_and_issues_are_read() {
+        let todos = scan("fn a() {} // TODO(@alice, #12): handle errors, see #34\n// FIXME: later\n");
+        assert_eq!(todos.len(), 2);
+        assert_eq!((todos[0].line, todos[0].tag.as_str()), (1, "TODO"));
+        assert_eq!(todos[0].owner.as_deref(), Some("alice"));
+        assert_eq!(todos[0].issues, [12, 34]);
+        assert_eq!(todos[0].text, "handle errors, see #34");
+        assert_eq!((todos[1].tag.as_str(), todos[1].owner.as_deref()), ("FIXME", None));
+    }
+
+    #[test]
+    fn prose_and_strings_are_not_todos() {
+        assert!(scan("let s = \"TODO: not me\";\n// mention TODO/FIXME in prose\n// TODOS are fine\n").is_empty());
+    }
+}
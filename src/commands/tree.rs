@@ -1,106 +1,107 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
 use std::fs;
 use std::path::{Path, PathBuf};
 use colored::*;
 use crate::context::CommandContext;
 use crate::output::{CommandOutput, EzError};
-
-pub fn execute(path: PathBuf, depth: usize, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
-    if !ctx.json {
-        println!("{} {}", "🌲".green(), path.display().to_string().cyan().bold());
-        print_tree(&path, "", 0, depth)?;
-    }
-
-    let tree_data = build_tree_json(&path, 0, depth)?;
-    Ok(CommandOutput::new("tree", tree_data))
+use crate::walk::WalkOptions;
+
+struct Node {
+    name: OsString,
+    path: PathBuf,
+    is_dir: bool,
+    /// Where a symlink to a folder points; shown but not descended into, so link loops can't recurse
+    dir_link: Option<PathBuf>,
 }
 
-fn build_tree_json(path: &Path, current_depth: usize, max_depth: usize) -> Result<serde_json::Value, EzError> {
-    if current_depth >= max_depth {
-        return Ok(serde_json::json!([]));
+pub fn execute(path: PathBuf, depth: usize, threads: usize, ctx: &CommandContext) -> Result<CommandOutput, EzError> {
+    if depth > 0 {
+        fs::read_dir(&path).map_err(|e| {
+            EzError::General(format!("Cannot read directory '{}': {}", path.display(), e))
+        })?;
     }
 
-    let entries = fs::read_dir(path).map_err(|e| {
-        EzError::General(format!("Cannot read directory '{}': {}", path.display(), e))
-    })?;
-
-    let mut items: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-    items.sort_by(|a, b| {
-        let a_is_dir = a.file_type().map(|t| t.is_dir()).unwrap_or(false);
-        let b_is_dir = b.file_type().map(|t| t.is_dir()).unwrap_or(false);
-        match (a_is_dir, b_is_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.file_name().cmp(&b.file_name()),
+    // One walk collects everything; the tree is then put together from each entry's parent
+    let options = WalkOptions { max_depth: Some(depth), threads, ..Default::default() };
+    let entries = crate::walk::walk(&path, &options, |entry| {
+        if entry.depth() == 0 {
+            return None;
         }
-    });
-
-    let mut result = Vec::new();
-    for entry in items {
-        let name = entry.file_name().to_string_lossy().to_string();
-        let is_dir = entry.metadata().map(|m| m.is_dir()).unwrap_or(false);
-
-        let children = if is_dir {
-            build_tree_json(&entry.path(), current_depth + 1, max_depth)?
+        let file_type = entry.file_type()?;
+        let dir_link = if file_type.is_symlink() && fs::metadata(entry.path()).is_ok_and(|m| m.is_dir()) {
+            fs::read_link(entry.path()).ok()
         } else {
-            serde_json::Value::Null
+            None
         };
+        Some((file_type.is_dir(), dir_link))
+    });
 
-        let mut node = serde_json::json!({
-            "name": name,
-            "type": if is_dir { "directory" } else { "file" },
+    let mut children: HashMap<PathBuf, Vec<Node>> = HashMap::new();
+    for (entry_path, (is_dir, dir_link)) in entries {
+        let parent = entry_path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let name = entry_path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+        children.entry(parent).or_default().push(Node { name, path: entry_path, is_dir, dir_link });
+    }
+    for items in children.values_mut() {
+        // Links to folders sort with the folders
+        items.sort_by(|a, b| match (a.is_dir || a.dir_link.is_some(), b.is_dir || b.dir_link.is_some()) {
+            (true, false) => std::cmp::Ordering::Less,
+            (false, true) => std::cmp::Ordering::Greater,
+            _ => a.name.cmp(&b.name),
         });
-        if is_dir {
-            node["children"] = children;
-        }
-        result.push(node);
     }
 
-    Ok(serde_json::json!(result))
-}
-
-fn print_tree(path: &Path, prefix: &str, current_depth: usize, max_depth: usize) -> Result<(), EzError> {
-    if current_depth >= max_depth {
-        return Ok(());
+    if !ctx.json {
+        println!("{} {}", "🌲".green(), path.display().to_string().cyan().bold());
+        print_tree(&children, &path, "");
     }
 
-    let entries = fs::read_dir(path).map_err(|e| {
-        EzError::General(format!("Cannot read directory '{}': {}", path.display(), e))
-    })?;
+    let tree_data = build_tree_json(&children, &path);
+    Ok(CommandOutput::new("tree", tree_data))
+}
 
-    let mut items: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-    items.sort_by(|a, b| {
-        let a_is_dir = a.file_type().map(|t| t.is_dir()).unwrap_or(false);
-        let b_is_dir = b.file_type().map(|t| t.is_dir()).unwrap_or(false);
-        match (a_is_dir, b_is_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.file_name().cmp(&b.file_name()),
+fn build_tree_json(children: &HashMap<PathBuf, Vec<Node>>, path: &Path) -> serde_json::Value {
+    let mut result = Vec::new();
+    for node in children.get(path).map(|c| c.as_slice()).unwrap_or_default() {
+        let mut item = serde_json::json!({
+            "name": node.name.to_string_lossy(),
+            "type": if node.is_dir { "directory" } else if node.dir_link.is_some() { "symlink" } else { "file" },
+        });
+        if let Some(target) = &node.dir_link {
+            item["target"] = serde_json::json!(target.display().to_string());
+            item["target_type"] = serde_json::json!("directory");
         }
-    });
+        if node.is_dir {
+            item["children"] = build_tree_json(children, &node.path);
+        }
+        result.push(item);
+    }
+    serde_json::json!(result)
+}
 
+fn print_tree(children: &HashMap<PathBuf, Vec<Node>>, path: &Path, prefix: &str) {
+    let items = children.get(path).map(|c| c.as_slice()).unwrap_or_default();
     let count = items.len();
-    for (i, entry) in items.iter().enumerate() {
+    for (i, node) in items.iter().enumerate() {
         let is_last = i == count - 1;
         let connector = if is_last { "└──" } else { "├──" };
         let extension = if is_last { "    " } else { "│   " };
 
-        let name = entry.file_name();
-        let name_str = name.to_string_lossy();
-        let is_dir = entry.metadata().map(|m| m.is_dir()).unwrap_or(false);
-
-        let display_name = if is_dir {
+        let name_str = node.name.to_string_lossy();
+        let display_name = if node.is_dir {
             format!("{}/", name_str).blue().bold()
+        } else if let Some(target) = &node.dir_link {
+            format!("{}/ -> {}", name_str, target.display()).cyan()
         } else {
             name_str.normal()
         };
 
         println!("{}{} {}", prefix, connector.dimmed(), display_name);
 
-        if is_dir {
+        if node.is_dir {
             let new_prefix = format!("{}{}", prefix, extension);
-            print_tree(&entry.path(), &new_prefix, current_depth + 1, max_depth)?;
+            print_tree(children, &node.path, &new_prefix);
         }
     }
-
-    Ok(())
 }
@@ -5,7 +5,6 @@ pub struct CommandContext {
     pub json: bool,
     pub yes: bool,
     pub dry_run: bool,
-    #[allow(dead_code)]
     pub is_tty: bool,
     pub is_stdin_tty: bool,
 }
@@ -1,7 +1,9 @@
 mod commands;
 mod context;
 mod output;
+mod scan;
 mod utils;
+mod walk;
 
 use commands::*;
 use context::CommandContext;
@@ -83,13 +85,13 @@ enum Commands {
     /// Find files or text (like find/grep)
     #[command(name = "find")]
     Find {
-        /// What to find
+        /// What to find (part of the name; '*' matches everything)
         pattern: String,
         /// Where to look (defaults to current folder)
         #[arg(default_value = ".")]
         path: PathBuf,
         /// Find in file contents (like grep)
-        #[arg(short, long)]
+        #[arg(long)]
         inside: bool,
         /// Case insensitive search
         #[arg(short, long)]
@@ -97,12 +99,69 @@ enum Commands {
         /// Show line numbers for content matches
         #[arg(short, long)]
         line_numbers: bool,
+        /// Treat the pattern as a glob, e.g. '*.test.js'
+        #[arg(long, conflicts_with = "regex")]
+        glob: bool,
+        /// Treat the pattern as a regular expression
+        #[arg(long)]
+        regex: bool,
+        /// Only files, folders or symlinks
+        #[arg(short = 't', long = "type", value_enum, value_name = "TYPE")]
+        entry_type: Option<EntryType>,
+        /// Only these extensions, e.g. rs,toml
+        #[arg(short = 'e', long = "ext", value_delimiter = ',', value_name = "EXT")]
+        extensions: Vec<String>,
+        /// Only files at least this big, e.g. 10MB
+        #[arg(long, value_name = "SIZE")]
+        min_size: Option<String>,
+        /// Only files at most this big, e.g. 1KB
+        #[arg(long, value_name = "SIZE")]
+        max_size: Option<String>,
+        /// Changed within this long ago (2d, 3h, 1week) or since a date
+        #[arg(long, value_name = "AGE")]
+        newer: Option<String>,
+        /// Changed longer ago than this (2d, 3h, 1week) or before a date
+        #[arg(long, value_name = "AGE")]
+        older: Option<String>,
+        /// Apply --newer/--older to creation time instead of modification time
+        #[arg(long)]
+        created: bool,
+        /// How many folder levels deep to look
+        #[arg(short, long)]
+        depth: Option<usize>,
+        /// Include hidden files and folders
+        #[arg(short = 'H', long)]
+        hidden: bool,
+        /// Don't skip files listed in .gitignore/.ignore
+        #[arg(long)]
+        no_ignore: bool,
+        /// Run a command for each match; {} is replaced by the path
+        #[arg(long, value_name = "CMD", group = "action")]
+        exec: Option<String>,
+        /// Run a command with all matches in place of {} (split into batches if too long)
+        #[arg(long, value_name = "CMD", group = "action")]
+        exec_batch: Option<String>,
+        /// Delete every match (asks first)
+        #[arg(long, group = "action")]
+        delete: bool,
+        /// Copy matches into a folder, keeping their relative paths
+        #[arg(long, value_name = "DIR", group = "action")]
+        copy_to: Option<PathBuf>,
+        /// Pack matches into an archive (.zip, .tar, .tar.gz, .tar.bz2)
+        #[arg(long, value_name = "ARCHIVE", group = "action")]
+        pack_into: Option<PathBuf>,
+        /// Change permissions of matches, e.g. 644 or u+x
+        #[arg(long, value_name = "MODE", group = "action")]
+        chmod: Option<String>,
+        /// Worker threads for walking folders (default: one per CPU)
+        #[arg(long, default_value = "0", hide_default_value = true)]
+        threads: usize,
     },
 
     /// Search for text in files recursively (like grep -r)
     #[command(name = "search", alias = "grep")]
     Search {
-        /// Pattern to search for
+        /// Text to search for (a regular expression with --regex)
         pattern: String,
         /// Where to search (defaults to current folder)
         #[arg(default_value = ".")]
@@ -110,13 +169,107 @@ enum Commands {
         /// Number of context lines to show
         #[arg(short, long, default_value = "2")]
         context: usize,
+        /// Case insensitive search
+        #[arg(short, long)]
+        ignore_case: bool,
+        /// Case insensitive unless the pattern has uppercase letters
+        #[arg(short = 'S', long)]
+        smart_case: bool,
+        /// Only match whole words
+        #[arg(short, long = "word-regexp")]
+        word: bool,
+        /// Show lines that do NOT match
+        #[arg(short = 'v', long = "invert-match")]
+        invert: bool,
+        /// Treat the pattern as a regular expression, not plain text
+        #[arg(short = 'E', long)]
+        regex: bool,
+        /// Let the pattern match across lines (use \n in it)
+        #[arg(short = 'U', long)]
+        multiline: bool,
+        /// Only list the files that match
+        #[arg(short = 'l', long, conflicts_with = "count")]
+        files_only: bool,
+        /// Only show how many lines match in each file
+        #[arg(long)]
+        count: bool,
+        /// Only search files matching this glob (repeatable), e.g. '*.rs'
+        #[arg(long, value_name = "GLOB")]
+        include: Vec<String>,
+        /// Skip files and folders matching this glob (repeatable), e.g. 'target'
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+        /// Also search inside .gz/.bz2 files and .zip/.tar/.tar.gz/.tar.bz2 archives
+        #[arg(short = 'z', long)]
+        archives: bool,
+        /// Only read files the `ez index` trigram index says could match (full scan if it's out of date)
+        #[arg(long)]
+        indexed: bool,
+        /// Worker threads for walking folders (default: one per CPU)
+        #[arg(long, default_value = "0", hide_default_value = true)]
+        threads: usize,
+    },
+
+    /// Keep a trigram index of a folder so `search --indexed` can skip files
+    #[command(name = "index")]
+    Index {
+        #[command(subcommand)]
+        action: IndexAction,
     },
 
-    /// Show file permissions (like ls -la)
-    #[command(name = "permissions", alias = "perms")]
+    /// List the functions, types and headings in source files, with line numbers
+    #[command(name = "outline", alias = "symbols")]
+    Outline {
+        /// File or folder to outline (.rs, .py, .js, .ts, .go, .md)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Worker threads for walking folders (default: one per CPU)
+        #[arg(long, default_value = "0", hide_default_value = true)]
+        threads: usize,
+    },
+
+    /// Collect TODO/FIXME/HACK/XXX comments, grouped by file, tag or owner
+    #[command(name = "todos", alias = "todo")]
+    Todos {
+        /// Folder or file to scan (defaults to current folder)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Tag to look for (repeatable; replaces the default TODO, FIXME, HACK and XXX)
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// How to group the results
+        #[arg(short, long, value_enum, default_value = "file")]
+        group_by: TodoGroup,
+        /// Worker threads for walking folders (default: one per CPU)
+        #[arg(long, default_value = "0", hide_default_value = true)]
+        threads: usize,
+    },
+
+    /// Look for credentials (AWS keys, private keys, JWTs, .env secrets) before sharing a folder
+    #[command(name = "secrets")]
+    Secrets {
+        /// Folder or file to scan (defaults to current folder)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Fingerprints, path:GLOB and rule:ID entries to skip (default: .ez-secrets-allow in PATH)
+        #[arg(long, value_name = "FILE")]
+        allowlist: Option<PathBuf>,
+        /// Skip files git ignores (they won't be committed, but pack and copy still take them)
+        #[arg(long)]
+        gitignore: bool,
+        /// Worker threads for walking folders (default: one per CPU)
+        #[arg(long, default_value = "0", hide_default_value = true)]
+        threads: usize,
+    },
+
+    /// Show or change file permissions (like ls -la / chmod)
+    #[command(name = "permissions", alias = "perms", args_conflicts_with_subcommands = true)]
     Permissions {
+        #[command(subcommand)]
+        action: Option<PermissionsAction>,
         /// File or directory to check
-        path: PathBuf,
+        #[arg(required = true)]
+        path: Option<PathBuf>,
     },
 
     /// Show directory tree structure
@@ -128,6 +281,9 @@ enum Commands {
         /// Maximum depth to display
         #[arg(short, long, default_value = "3")]
         depth: usize,
+        /// Worker threads for walking folders (default: one per CPU)
+        #[arg(long, default_value = "0", hide_default_value = true)]
+        threads: usize,
     },
 
     /// Show environment variables (like env/printenv)
@@ -167,13 +323,78 @@ enum Commands {
     Replace {
         /// Text to find
         old: String,
-        /// Text to replace with
+        /// Text to replace with ($1, ${name} refer to captures with --regex)
         new: String,
-        /// File to modify
-        file: PathBuf,
+        /// Files, folders or globs to modify (reads from stdin if omitted)
+        files: Vec<PathBuf>,
         /// Replace all occurrences
         #[arg(short, long)]
         all: bool,
+        /// Treat the text to find as a regular expression
+        #[arg(long)]
+        regex: bool,
+        /// Don't skip files excluded by .gitignore/.ignore
+        #[arg(long)]
+        no_ignore: bool,
+        /// Ask before each replacement, showing it as a diff
+        #[arg(short, long)]
+        interactive: bool,
+        /// Keep a copy of each original file (default suffix: .bak)
+        #[arg(long, value_name = "SUFFIX", num_args = 0..=1, require_equals = true, default_missing_value = ".bak")]
+        backup: Option<String>,
+    },
+
+    /// Make precise line-based edits to a file
+    #[command(name = "edit")]
+    #[command(group(clap::ArgGroup::new("operation").required(true)))]
+    Edit {
+        /// File to edit
+        file: PathBuf,
+        /// Insert text after line N (0 inserts at the top)
+        #[arg(long, value_name = "N", group = "operation")]
+        insert_after: Option<usize>,
+        /// Insert text after the first line matching a regex
+        #[arg(long, value_name = "REGEX", group = "operation")]
+        insert_after_match: Option<String>,
+        /// Delete a line range like 10-20
+        #[arg(long, value_name = "RANGE", group = "operation")]
+        delete_lines: Option<String>,
+        /// Replace everything between two marker lines (the start marker must be unique)
+        #[arg(long, num_args = 2, value_names = ["START", "END"], group = "operation")]
+        between: Option<Vec<String>>,
+        /// Append a line unless the file already has it
+        #[arg(long, value_name = "LINE", group = "operation")]
+        append_if_missing: Option<String>,
+        /// Print the file's SHA-256 for use with --expect-hash
+        #[arg(long, group = "operation")]
+        hash: bool,
+        /// Text to insert (reads from stdin if omitted)
+        #[arg(short, long)]
+        text: Option<String>,
+        /// Refuse to edit unless the file's SHA-256 matches
+        #[arg(long, value_name = "SHA256")]
+        expect_hash: Option<String>,
+        /// Keep a copy of the original (default suffix: .bak)
+        #[arg(long, value_name = "SUFFIX", num_args = 0..=1, require_equals = true, default_missing_value = ".bak")]
+        backup: Option<String>,
+    },
+
+    /// Get, set or delete keys in JSON, TOML and YAML files
+    #[command(name = "config-edit")]
+    ConfigEdit {
+        /// Config file to read or edit
+        file: PathBuf,
+        /// What to do with the key
+        #[arg(value_enum)]
+        action: ConfigAction,
+        /// Key path like server.ports[0] (KEY=VALUE for set)
+        expression: String,
+        /// File format (auto-detected from extension)
+        #[arg(short, long, value_enum)]
+        format: Option<ConfigFormat>,
+        /// Keep a copy of the original (default suffix: .bak)
+        #[arg(long, value_name = "SUFFIX", num_args = 0..=1, require_equals = true, default_missing_value = ".bak")]
+        backup: Option<String>,
     },
 
     /// Copy files or folders
@@ -206,6 +427,29 @@ enum Commands {
         if_not_exists: bool,
     },
 
+    /// Rename many files at once using a pattern
+    #[command(name = "rename")]
+    Rename {
+        /// Regex to match in file names (supports capture groups)
+        pattern: String,
+        /// Replacement: $1 captures, {n} sequence, {date} mtime, {name}, {ext}
+        replacement: String,
+        /// Files to rename (globs allowed; reads from stdin if omitted)
+        paths: Vec<PathBuf>,
+        /// Replace every match in the name, not just the first
+        #[arg(short, long)]
+        all: bool,
+        /// First number used for {n}
+        #[arg(long, default_value = "1", value_name = "N")]
+        start: usize,
+        /// Change the case of the new name
+        #[arg(long, value_enum)]
+        case: Option<RenameCase>,
+        /// Change the extension (empty to remove it)
+        #[arg(long, value_name = "EXT")]
+        ext: Option<String>,
+    },
+
     /// Remove files or folders (like rm)
     #[command(name = "remove", alias = "rm")]
     Remove {
@@ -240,6 +484,33 @@ enum Commands {
         /// Skip if file already exists
         #[arg(long)]
         if_not_exists: bool,
+        /// Start from a template in ~/.config/ez/templates ({{name}} and {{date}} are filled in)
+        #[arg(long, value_name = "NAME", conflicts_with = "content")]
+        template: Option<String>,
+        /// Initial content (use - to read it from stdin)
+        #[arg(long, value_name = "TEXT")]
+        content: Option<String>,
+        /// Set access and modification time, e.g. "2024-01-31 09:00" or @1706688000
+        #[arg(long, value_name = "TIME", conflicts_with = "reference")]
+        time: Option<String>,
+        /// Copy access and modification time from another file
+        #[arg(long, value_name = "FILE")]
+        reference: Option<PathBuf>,
+        /// Permissions for the new file in octal, e.g. 600 or 0755
+        #[arg(long, value_name = "MODE")]
+        mode: Option<String>,
+    },
+
+    /// Create folders and files from a YAML or JSON spec
+    Scaffold {
+        /// Spec file describing the tree (names ending in / are folders)
+        spec: PathBuf,
+        /// Folder to create the tree in
+        #[arg(long, default_value = ".")]
+        to: PathBuf,
+        /// Keep files that already exist instead of failing
+        #[arg(long)]
+        if_not_exists: bool,
     },
 
     /// Show current location (like pwd)
@@ -255,6 +526,9 @@ enum Commands {
         /// Show detailed breakdown
         #[arg(short, long)]
         detailed: bool,
+        /// Worker threads for walking folders (default: one per CPU)
+        #[arg(long, default_value = "0", hide_default_value = true)]
+        threads: usize,
     },
 
     /// Show running programs (like ps)
@@ -313,10 +587,61 @@ enum Commands {
         to: Option<PathBuf>,
     },
 
+    /// Split a large file into numbered chunks with a checksum manifest (like split)
+    #[command(name = "split")]
+    Split {
+        /// File to split
+        file: PathBuf,
+        /// Maximum chunk size, e.g. 100MB, 1.5G, 500K
+        #[arg(short, long)]
+        size: String,
+        /// Folder for the chunks and manifest (defaults to the file's folder)
+        #[arg(long)]
+        to: Option<PathBuf>,
+    },
+
+    /// Reassemble a split file from its manifest, verifying every chunk
+    #[command(name = "join")]
+    Join {
+        /// Manifest written by ez split (<file>.manifest.json)
+        manifest: PathBuf,
+        /// Where to write the joined file (defaults to the original name)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
     /// Show disk space (like df)
     #[command(name = "space", alias = "diskfree")]
     Space,
 
+    /// Convert files to UTF-8 with consistent line endings and no trailing whitespace
+    #[command(name = "normalize")]
+    Normalize {
+        /// Files, folders or globs to normalize
+        paths: Vec<PathBuf>,
+        /// Only list files that need normalizing; change nothing. Exits with code 6 if any do
+        #[arg(long)]
+        check: bool,
+        /// Line endings to use
+        #[arg(long, value_enum, default_value = "lf")]
+        eol: LineEnding,
+        /// Keep a UTF-8 byte order mark if the file has one
+        #[arg(long)]
+        keep_bom: bool,
+        /// Leave trailing spaces and tabs alone
+        #[arg(long)]
+        keep_whitespace: bool,
+        /// Encoding of files that aren't valid UTF-8 (default: windows-1252, a superset of Latin-1; files that also hold UTF-8 are skipped)
+        #[arg(long, value_name = "ENCODING")]
+        from: Option<String>,
+        /// Include files ignored by .gitignore/.ignore
+        #[arg(long)]
+        no_ignore: bool,
+        /// Keep a copy of each original file (default suffix: .bak)
+        #[arg(long, value_name = "SUFFIX", num_args = 0..=1, require_equals = true, default_missing_value = ".bak")]
+        backup: Option<String>,
+    },
+
     /// Count lines, words, bytes in files (like wc)
     #[command(name = "count")]
     Count {
@@ -347,6 +672,12 @@ enum Commands {
         /// Remove duplicate lines
         #[arg(short, long)]
         unique: bool,
+        /// Write the sorted lines back to the file
+        #[arg(short, long)]
+        in_place: bool,
+        /// With --in-place, keep a copy of the original (default suffix: .bak)
+        #[arg(long, value_name = "SUFFIX", num_args = 0..=1, require_equals = true, default_missing_value = ".bak")]
+        backup: Option<String>,
     },
 
     /// Show differences between files (like diff)
@@ -361,14 +692,36 @@ enum Commands {
         side_by_side: bool,
     },
 
+    /// Create a symlink or hard link (like ln), or audit links with `check`
+    #[command(name = "link", alias = "ln", args_conflicts_with_subcommands = true)]
+    Link {
+        #[command(subcommand)]
+        action: Option<LinkAction>,
+        /// What the link points to
+        #[arg(required = true)]
+        target: Option<PathBuf>,
+        /// Where to create the link (a folder keeps the target's name)
+        #[arg(required = true)]
+        name: Option<PathBuf>,
+        /// Make a hard link (a second name for the same file) instead of a symlink
+        #[arg(long)]
+        hard: bool,
+    },
+
     /// Make file executable (like chmod +x)
     #[command(name = "make-runnable")]
     MakeRunnable {
-        /// File to make executable
+        /// File to make executable (or a folder, with --all)
         file: PathBuf,
         /// Skip if already executable
         #[arg(long)]
         if_not_exists: bool,
+        /// Add a missing shebang line guessed from the extension without asking
+        #[arg(long)]
+        add_shebang: bool,
+        /// Make every script in the folder runnable
+        #[arg(long)]
+        all: bool,
     },
 
     /// Explain any Unix command in plain English
@@ -400,6 +753,93 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum PermissionsAction {
+    /// Change permissions, explaining the before and after
+    Set {
+        /// Files or folders to change
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// Octal (755) or symbolic (u+x,go-w) mode
+        #[arg(short, long)]
+        mode: Option<String>,
+        /// Apply to everything inside folders too
+        #[arg(short, long)]
+        recursive: bool,
+        /// Mode for files only (with --recursive), e.g. 644
+        #[arg(long, value_name = "MODE")]
+        files: Option<String>,
+        /// Mode for folders only (with --recursive), e.g. 755
+        #[arg(long, value_name = "MODE")]
+        dirs: Option<String>,
+        /// Let the owner read
+        #[arg(long)]
+        owner_read: bool,
+        /// Let the owner write
+        #[arg(long)]
+        owner_write: bool,
+        /// Let the owner execute
+        #[arg(long)]
+        owner_exec: bool,
+        /// Let the group read
+        #[arg(long)]
+        group_read: bool,
+        /// Let the group write
+        #[arg(long)]
+        group_write: bool,
+        /// Let the group execute
+        #[arg(long)]
+        group_exec: bool,
+        /// Let others read
+        #[arg(long)]
+        others_read: bool,
+        /// Let others write
+        #[arg(long)]
+        others_write: bool,
+        /// Let others execute
+        #[arg(long)]
+        others_exec: bool,
+        /// Take away all owner permissions (before any --owner-* flags)
+        #[arg(long)]
+        no_owner: bool,
+        /// Take away all group permissions (before any --group-* flags)
+        #[arg(long)]
+        no_group: bool,
+        /// Take away all permissions from others (before any --others-* flags)
+        #[arg(long)]
+        no_others: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum LinkAction {
+    /// Find broken symlinks, loops and links leaving the folder
+    Check {
+        /// Folder to check (defaults to current)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Repoint links where the right target can be worked out
+        #[arg(long)]
+        fix: bool,
+        /// Delete broken and looping links
+        #[arg(long)]
+        remove: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum IndexAction {
+    /// Build or refresh the index (only changed files are re-read)
+    Build {
+        /// Folder to index (defaults to current)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Worker threads for walking folders (default: one per CPU)
+        #[arg(long, default_value = "0", hide_default_value = true)]
+        threads: usize,
+    },
+}
+
 #[derive(Clone, Copy, Debug, clap::ValueEnum)]
 pub enum ArchiveFormat {
     Zip,
@@ -408,6 +848,50 @@ pub enum ArchiveFormat {
     TarBz2,
 }
 
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum ConfigAction {
+    Get,
+    Set,
+    Delete,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum EntryType {
+    File,
+    Dir,
+    Symlink,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Keep,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum TodoGroup {
+    File,
+    Tag,
+    Owner,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum RenameCase {
+    Lower,
+    Upper,
+    Title,
+    Snake,
+    Kebab,
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -425,6 +909,10 @@ fn main() {
         Commands::Peek { .. } => "peek",
         Commands::Find { .. } => "find",
         Commands::Search { .. } => "search",
+        Commands::Index { .. } => "index",
+        Commands::Outline { .. } => "outline",
+        Commands::Todos { .. } => "todos",
+        Commands::Secrets { .. } => "secrets",
         Commands::Permissions { .. } => "permissions",
         Commands::Tree { .. } => "tree",
         Commands::Env { .. } => "env",
@@ -433,11 +921,15 @@ fn main() {
         Commands::Watch { .. } => "watch",
         Commands::Disk => "disk",
         Commands::Replace { .. } => "replace",
+        Commands::Edit { .. } => "edit",
+        Commands::ConfigEdit { .. } => "config-edit",
         Commands::Copy { .. } => "copy",
         Commands::Move { .. } => "move",
+        Commands::Rename { .. } => "rename",
         Commands::Remove { .. } => "remove",
         Commands::CreateFolder { .. } => "create-folder",
         Commands::CreateFile { .. } => "create-file",
+        Commands::Scaffold { .. } => "scaffold",
         Commands::Where => "where",
         Commands::Size { .. } => "size",
         Commands::Running { .. } => "running",
@@ -445,11 +937,15 @@ fn main() {
         Commands::Download { .. } => "download",
         Commands::Pack { .. } => "pack",
         Commands::Unpack { .. } => "unpack",
+        Commands::Split { .. } => "split",
+        Commands::Join { .. } => "join",
         Commands::Space => "space",
+        Commands::Normalize { .. } => "normalize",
         Commands::Count { .. } => "count",
         Commands::Sort { .. } => "sort",
         Commands::Compare { .. } => "compare",
         Commands::MakeRunnable { .. } => "make-runnable",
+        Commands::Link { .. } => "link",
         Commands::Explain { .. } => "explain",
         Commands::Chain { .. } => "chain",
         Commands::HelpMe { .. } => "help-me",
@@ -466,17 +962,72 @@ fn main() {
         Commands::Peek { file, lines, tail } => {
             peek::execute(file, lines, tail, &ctx)
         }
-        Commands::Find { pattern, path, inside, ignore_case, line_numbers } => {
-            find::execute(pattern, path, inside, ignore_case, line_numbers, &ctx)
+        Commands::Find {
+            pattern, path, inside, ignore_case, line_numbers, glob, regex, entry_type, extensions,
+            min_size, max_size, newer, older, created, depth, hidden, no_ignore,
+            exec, exec_batch, delete, copy_to, pack_into, chmod, threads,
+        } => {
+            let filters = find::Filters {
+                glob, regex, entry_type, extensions, min_size, max_size, newer, older, created, depth, hidden, no_ignore,
+            };
+            let action = if let Some(cmd) = exec {
+                Some(find::Action::Exec(cmd))
+            } else if let Some(cmd) = exec_batch {
+                Some(find::Action::ExecBatch(cmd))
+            } else if delete {
+                Some(find::Action::Delete)
+            } else if let Some(dir) = copy_to {
+                Some(find::Action::CopyTo(dir))
+            } else if let Some(archive) = pack_into {
+                Some(find::Action::PackInto(archive))
+            } else {
+                chmod.map(find::Action::Chmod)
+            };
+            find::execute(pattern, path, inside, ignore_case, line_numbers, filters, action, threads, &ctx)
+        }
+        Commands::Search {
+            pattern, path, context, ignore_case, smart_case, word, invert, regex, multiline,
+            files_only, count, include, exclude, archives, indexed, threads,
+        } => {
+            let options = search::SearchOptions {
+                ignore_case, smart_case, word, invert, regex, multiline, files_only, count, include, exclude, archives,
+                indexed,
+            };
+            search::execute(pattern, path, context, options, threads, &ctx)
+        }
+        Commands::Index { action: IndexAction::Build { path, threads } } => {
+            index::build(path, threads, &ctx)
         }
-        Commands::Search { pattern, path, context } => {
-            search::execute(pattern, path, context, &ctx)
+        Commands::Outline { path, threads } => {
+            outline::execute(path, threads, &ctx)
         }
-        Commands::Permissions { path } => {
-            permissions::execute(path, &ctx)
+        Commands::Todos { path, tags, group_by, threads } => {
+            todos::execute(path, tags, group_by, threads, &ctx)
         }
-        Commands::Tree { path, depth } => {
-            tree::execute(path, depth, &ctx)
+        Commands::Secrets { path, allowlist, gitignore, threads } => {
+            secrets::execute(path, allowlist, gitignore, threads, &ctx)
+        }
+        Commands::Permissions { action: None, path } => {
+            permissions::execute(path.unwrap_or_default(), &ctx)
+        }
+        Commands::Permissions { action: Some(PermissionsAction::Set {
+            paths, mode, recursive, files, dirs,
+            owner_read, owner_write, owner_exec, group_read, group_write, group_exec,
+            others_read, others_write, others_exec, no_owner, no_group, no_others,
+        }), .. } => {
+            // Friendly flags become symbolic clauses: clears first, then grants
+            let flags = [
+                (no_owner, "u="), (no_group, "g="), (no_others, "o="),
+                (owner_read, "u+r"), (owner_write, "u+w"), (owner_exec, "u+x"),
+                (group_read, "g+r"), (group_write, "g+w"), (group_exec, "g+x"),
+                (others_read, "o+r"), (others_write, "o+w"), (others_exec, "o+x"),
+            ];
+            let mut changes: Vec<String> = mode.into_iter().collect();
+            changes.extend(flags.iter().filter(|(on, _)| *on).map(|(_, clause)| clause.to_string()));
+            permissions::set(paths, changes, recursive, files, dirs, &ctx)
+        }
+        Commands::Tree { path, depth, threads } => {
+            tree::execute(path, depth, threads, &ctx)
         }
         Commands::Env { pattern } => {
             env::execute(pattern, &ctx)
@@ -493,8 +1044,17 @@ fn main() {
         Commands::Disk => {
             disk::execute(&ctx)
         }
-        Commands::Replace { old, new, file, all } => {
-            replace::execute(old, new, file, all, &ctx)
+        Commands::Replace { old, new, mut files, all, regex, no_ignore, interactive, backup } => {
+            if files.is_empty() && !ctx.is_stdin_tty {
+                files = utils::read_paths_from_stdin();
+            }
+            replace::execute(old, new, files, all, regex, no_ignore, interactive, backup, &ctx)
+        }
+        Commands::Edit { file, insert_after, insert_after_match, delete_lines, between, append_if_missing, hash: _, text, expect_hash, backup } => {
+            edit::execute(file, insert_after, insert_after_match, delete_lines, between, append_if_missing, text, expect_hash, backup, &ctx)
+        }
+        Commands::ConfigEdit { file, action, expression, format, backup } => {
+            config_edit::execute(file, action, expression, format, backup, &ctx)
         }
         Commands::Copy { from, to, recursive, progress, if_not_exists } => {
             copy::execute(from, to, recursive, progress, if_not_exists, &ctx)
@@ -502,6 +1062,12 @@ fn main() {
         Commands::Move { from, to, if_not_exists } => {
             r#move::execute(from, to, if_not_exists, &ctx)
         }
+        Commands::Rename { pattern, replacement, mut paths, all, start, case, ext } => {
+            if paths.is_empty() && !ctx.is_stdin_tty {
+                paths = utils::read_paths_from_stdin();
+            }
+            rename::execute(pattern, replacement, paths, all, start, case, ext, &ctx)
+        }
         Commands::Remove { mut paths, recursive, force } => {
             if paths.is_empty() && !ctx.is_stdin_tty {
                 paths = utils::read_paths_from_stdin();
@@ -514,17 +1080,21 @@ fn main() {
             }
             create_folder::execute(paths, parents, if_not_exists, &ctx)
         }
-        Commands::CreateFile { mut paths, if_not_exists } => {
-            if paths.is_empty() && !ctx.is_stdin_tty {
+        Commands::CreateFile { mut paths, if_not_exists, template, content, time, reference, mode } => {
+            // With --content - stdin carries the content, not the paths
+            if paths.is_empty() && !ctx.is_stdin_tty && content.as_deref() != Some("-") {
                 paths = utils::read_paths_from_stdin();
             }
-            create_file::execute(paths, if_not_exists, &ctx)
+            create_file::execute(paths, if_not_exists, template, content, time, reference, mode, &ctx)
+        }
+        Commands::Scaffold { spec, to, if_not_exists } => {
+            scaffold::execute(spec, to, if_not_exists, &ctx)
         }
         Commands::Where => {
             r#where::execute(&ctx)
         }
-        Commands::Size { path, detailed } => {
-            size::execute(path, detailed, &ctx)
+        Commands::Size { path, detailed, threads } => {
+            size::execute(path, detailed, threads, &ctx)
         }
         Commands::Running { all, filter } => {
             running::execute(all, filter, &ctx)
@@ -544,23 +1114,41 @@ fn main() {
         Commands::Unpack { archive, to } => {
             unpack::execute(archive, to, &ctx)
         }
+        Commands::Split { file, size, to } => {
+            split::execute(file, size, to, &ctx)
+        }
+        Commands::Join { manifest, output } => {
+            join::execute(manifest, output, &ctx)
+        }
         Commands::Space => {
             space::execute(&ctx)
         }
+        Commands::Normalize { mut paths, check, eol, keep_bom, keep_whitespace, from, no_ignore, backup } => {
+            if paths.is_empty() && !ctx.is_stdin_tty {
+                paths = utils::read_paths_from_stdin();
+            }
+            normalize::execute(paths, check, eol, keep_bom, keep_whitespace, from, no_ignore, backup, &ctx)
+        }
         Commands::Count { mut files, lines, words, bytes } => {
             if files.is_empty() && !ctx.is_stdin_tty {
                 files = utils::read_paths_from_stdin();
             }
             count::execute(files, lines, words, bytes, &ctx)
         }
-        Commands::Sort { file, reverse, numeric, unique } => {
-            sort::execute(file, reverse, numeric, unique, &ctx)
+        Commands::Sort { file, reverse, numeric, unique, in_place, backup } => {
+            sort::execute(file, reverse, numeric, unique, in_place, backup, &ctx)
         }
         Commands::Compare { file1, file2, side_by_side } => {
             compare::execute(file1, file2, side_by_side, &ctx)
         }
-        Commands::MakeRunnable { file, if_not_exists } => {
-            make_runnable::execute(file, if_not_exists, &ctx)
+        Commands::Link { action: None, target, name, hard } => {
+            link::execute(target.unwrap_or_default(), name.unwrap_or_default(), hard, &ctx)
+        }
+        Commands::Link { action: Some(LinkAction::Check { path, fix, remove }), .. } => {
+            link::check(path, fix, remove, &ctx)
+        }
+        Commands::MakeRunnable { file, if_not_exists, add_shebang, all } => {
+            make_runnable::execute(file, if_not_exists, add_shebang, all, &ctx)
         }
         Commands::Explain { command } => {
             explain::execute(command, &ctx)
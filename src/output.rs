@@ -15,6 +15,10 @@ pub struct CommandOutput {
     pub data: serde_json::Value,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
+    /// Exit code once the output is printed. Commands that finish but couldn't do
+    /// everything (like `ez replace` with an unwritable file) exit non-zero.
+    #[serde(skip)]
+    pub exit_code: i32,
 }
 
 impl CommandOutput {
@@ -25,6 +29,7 @@ impl CommandOutput {
             success: true,
             data,
             metadata: None,
+            exit_code: 0,
         }
     }
 
@@ -36,6 +41,7 @@ impl CommandOutput {
             success: true,
             data: serde_json::json!({}),
             metadata: None,
+            exit_code: 0,
         }
     }
 
@@ -43,8 +49,16 @@ impl CommandOutput {
         self.metadata = Some(metadata);
         self
     }
+
+    pub fn with_exit_code(mut self, code: i32) -> Self {
+        self.exit_code = code;
+        self
+    }
 }
 
+/// Exit code for a check that ran fine but found problems (e.g. `ez normalize --check` or `ez secrets`)
+pub const EXIT_FOUND: i32 = 6;
+
 /// Typed error enum with granular exit codes.
 pub enum EzError {
     /// Generic failure — exit code 1
@@ -112,6 +126,9 @@ pub fn output_result(json_mode: bool, _command: &'static str, result: Result<Com
                 println!("{}", serde_json::to_string(&output).unwrap());
             }
             // Human output is already printed by the command itself
+            if output.exit_code != 0 {
+                std::process::exit(output.exit_code);
+            }
         }
         Err(e) => {
             if json_mode {
@@ -0,0 +1,147 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+/// One line of a file, without its line ending.
+pub struct Line<'a> {
+    /// 1-based line number
+    pub number: usize,
+    /// Byte offset of the line's start within the file
+    pub offset: u64,
+    pub text: Cow<'a, str>,
+}
+
+/// Reads a file one line at a time, so memory use is bounded by the longest
+/// line rather than the file size. Bytes that aren't valid UTF-8 are decoded
+/// lossily (as U+FFFD) instead of failing the whole file.
+pub struct LineReader<R> {
+    reader: BufReader<R>,
+    buf: Vec<u8>,
+    number: usize,
+    offset: u64,
+    /// A NUL byte was seen: at the start (grep's heuristic) or anywhere read so far
+    pub binary: bool,
+    /// Some line wasn't valid UTF-8 and was decoded lossily
+    pub lossy: bool,
+}
+
+impl LineReader<File> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read> LineReader<R> {
+    pub fn new(inner: R) -> io::Result<Self> {
+        let mut reader = BufReader::with_capacity(64 * 1024, inner);
+        let binary = crate::utils::is_binary(reader.fill_buf()?);
+        Ok(LineReader { reader, buf: Vec::new(), number: 0, offset: 0, binary, lossy: false })
+    }
+
+    pub fn next_line(&mut self) -> io::Result<Option<Line<'_>>> {
+        self.buf.clear();
+        let read = self.reader.read_until(b'\n', &mut self.buf)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        let offset = self.offset;
+        self.offset += read as u64;
+        self.number += 1;
+
+        let mut end = self.buf.len();
+        while end > 0 && matches!(self.buf[end - 1], b'\n' | b'\r') {
+            end -= 1;
+        }
+        let bytes = &self.buf[..end];
+        if !self.binary && bytes.contains(&0) {
+            self.binary = true;
+        }
+        let text = String::from_utf8_lossy(bytes);
+        if matches!(text, Cow::Owned(_)) {
+            self.lossy = true;
+        }
+        Ok(Some(Line { number: self.number, offset, text }))
+    }
+}
+
+/// Compressed files and archives `ez search --archives` can look inside,
+/// judged by file name.
+#[derive(Clone, Copy)]
+pub enum Container {
+    Gzip,
+    Bzip2,
+    Zip,
+    Tar,
+    TarGz,
+    TarBz2,
+}
+
+impl Container {
+    pub fn detect(path: &Path) -> Option<Container> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        let kind = if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Container::TarGz
+        } else if name.ends_with(".tar.bz2") || name.ends_with(".tbz2") || name.ends_with(".tbz") {
+            Container::TarBz2
+        } else if name.ends_with(".tar") {
+            Container::Tar
+        } else if name.ends_with(".zip") {
+            Container::Zip
+        } else if name.ends_with(".gz") {
+            Container::Gzip
+        } else if name.ends_with(".bz2") {
+            Container::Bzip2
+        } else {
+            return None;
+        };
+        Some(kind)
+    }
+
+    /// Single compressed files have no inner names; archives hold many files.
+    pub fn is_archive(self) -> bool {
+        !matches!(self, Container::Gzip | Container::Bzip2)
+    }
+
+    /// Stream each file inside to `visit`, with its path inside the archive
+    /// (`None` for a plain .gz/.bz2). Nothing is extracted to disk.
+    pub fn for_each_file<F>(self, path: &Path, mut visit: F) -> io::Result<()>
+    where
+        F: FnMut(Option<&str>, &mut dyn Read) -> io::Result<()>,
+    {
+        let file = File::open(path)?;
+        match self {
+            Container::Gzip => visit(None, &mut flate2::read::MultiGzDecoder::new(file)),
+            Container::Bzip2 => visit(None, &mut bzip2::read::MultiBzDecoder::new(file)),
+            Container::Zip => {
+                let mut zip = zip::ZipArchive::new(file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                for i in 0..zip.len() {
+                    let mut entry = zip.by_index(i).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                    if entry.is_file() {
+                        let name = entry.name().to_string();
+                        visit(Some(&name), &mut entry)?;
+                    }
+                }
+                Ok(())
+            }
+            Container::Tar => each_tar_file(file, visit),
+            Container::TarGz => each_tar_file(flate2::read::GzDecoder::new(file), visit),
+            Container::TarBz2 => each_tar_file(bzip2::read::BzDecoder::new(file), visit),
+        }
+    }
+}
+
+fn each_tar_file<R: Read, F>(reader: R, mut visit: F) -> io::Result<()>
+where
+    F: FnMut(Option<&str>, &mut dyn Read) -> io::Result<()>,
+{
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_file() {
+            let name = entry.path()?.to_string_lossy().to_string();
+            visit(Some(&name), &mut entry)?;
+        }
+    }
+    Ok(())
+}
@@ -1,6 +1,7 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use chrono::{DateTime, Local, Utc};
+use indicatif::{ProgressBar, ProgressStyle};
 
 pub fn format_size(size: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
@@ -15,6 +16,24 @@ pub fn format_size(size: u64) -> String {
     format!("{:.1} {}", size, UNITS[unit_index])
 }
 
+/// Parse a human size like `100MB`, `1.5G`, `512k` or `2048` (bytes).
+/// Units are binary, matching `format_size`: 1 KB = 1024 bytes.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return None,
+    };
+    Some((number * multiplier as f64) as u64)
+}
+
 pub fn format_time(time: SystemTime) -> String {
     let datetime: DateTime<Local> = time.into();
     datetime.format("%b %d %H:%M").to_string()
@@ -26,6 +45,39 @@ pub fn format_time_iso8601(time: SystemTime) -> String {
     datetime.format("%Y-%m-%dT%H:%M:%SZ").to_string()
 }
 
+/// Parse `now`, `@<unix seconds>`, RFC 3339, or a local `YYYY-MM-DD[ HH:MM[:SS]]`.
+pub fn parse_time(value: &str) -> Result<chrono::DateTime<chrono::Local>, crate::output::EzError> {
+    use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+
+    let value = value.trim();
+    if value == "now" {
+        return Ok(Local::now());
+    }
+    if let Some(secs) = value.strip_prefix('@') {
+        if let Some(t) = secs.parse().ok().and_then(|s| Local.timestamp_opt(s, 0).single()) {
+            return Ok(t);
+        }
+    }
+    if let Ok(t) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(t.with_timezone(&Local));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            if let Some(t) = Local.from_local_datetime(&naive).earliest() {
+                return Ok(t);
+            }
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        if let Some(t) = date.and_hms_opt(0, 0, 0).and_then(|d| Local.from_local_datetime(&d).earliest()) {
+            return Ok(t);
+        }
+    }
+    Err(crate::output::EzError::InvalidArgs(format!(
+        "Invalid time '{}'. Use 'YYYY-MM-DD', 'YYYY-MM-DD HH:MM:SS', RFC 3339 or @<unix seconds>", value
+    )))
+}
+
 #[allow(dead_code)]
 pub fn format_duration(seconds: u64) -> String {
     if seconds < 60 {
@@ -48,3 +100,260 @@ pub fn read_paths_from_stdin() -> Vec<PathBuf> {
         .map(PathBuf::from)
         .collect()
 }
+
+/// SHA-256 of a byte slice as a lowercase hex string.
+pub fn sha256_bytes(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// SHA-256 of a file's contents as a lowercase hex string.
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 65536];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Progress bar for byte-oriented transfers (downloads, copies).
+pub fn byte_progress_bar(total: u64) -> ProgressBar {
+    let pb = ProgressBar::new(total);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+    pb
+}
+
+/// Expand any glob patterns (`*.jpg`, `logs/**/*.txt`) among `paths`.
+/// Paths that exist as given, or patterns that match nothing, are kept verbatim
+/// so the command can report them as missing.
+pub fn expand_globs(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut expanded = Vec::new();
+    for path in paths {
+        let pattern = path.to_string_lossy().to_string();
+        let is_glob = pattern.contains(['*', '?', '[']);
+        if path.exists() || !is_glob {
+            expanded.push(path);
+            continue;
+        }
+        match glob::glob(&pattern) {
+            Ok(matches) => {
+                let before = expanded.len();
+                expanded.extend(matches.filter_map(|m| m.ok()));
+                if expanded.len() == before {
+                    expanded.push(path);
+                }
+            }
+            Err(_) => expanded.push(path),
+        }
+    }
+    expanded
+}
+
+/// Collect regular files from `paths`, descending into folders.
+/// Folder walks skip `.git` and, when `respect_ignore` is set, anything
+/// excluded by `.gitignore`/`.ignore`. Files named explicitly are always kept.
+pub fn collect_files(paths: &[PathBuf], respect_ignore: bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let walker = ignore::WalkBuilder::new(path)
+            .hidden(false)
+            .git_ignore(respect_ignore)
+            .git_global(respect_ignore)
+            .git_exclude(respect_ignore)
+            .ignore(respect_ignore)
+            .parents(respect_ignore)
+            .require_git(false)
+            .filter_entry(|e| e.file_name() != ".git")
+            .build();
+        for entry in walker.filter_map(|e| e.ok()) {
+            if entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+                files.push(entry.into_path());
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Heuristic used by grep and git: a NUL byte near the start means binary.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|&b| b == 0)
+}
+
+/// Replace a file's contents without ever leaving it half-written.
+///
+/// The data goes to a temp file in the same folder, which gets the original's
+/// mode (and ownership, when permitted), is fsynced, and is then renamed over
+/// the original. Symlinks are followed so the link itself survives. With
+/// `backup_suffix`, the previous version is first copied to `<file><suffix>`;
+/// its path is returned.
+pub fn write_atomic(path: &Path, contents: &[u8], backup_suffix: Option<&str>) -> std::io::Result<Option<PathBuf>> {
+    use std::io::Write;
+    use std::os::unix::fs::MetadataExt;
+
+    let target = if path.is_symlink() { std::fs::canonicalize(path)? } else { path.to_path_buf() };
+    let original = std::fs::metadata(&target).ok();
+
+    let backup = match (backup_suffix, &original) {
+        (Some(suffix), Some(_)) => {
+            let mut name = target.as_os_str().to_os_string();
+            name.push(suffix);
+            let backup = PathBuf::from(name);
+            std::fs::copy(&target, &backup)?;
+            Some(backup)
+        }
+        _ => None,
+    };
+
+    let dir = match target.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let name = target.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let temp = dir.join(format!(".{}.ez-tmp-{}", name, uuid::Uuid::new_v4().simple()));
+
+    let result = (|| {
+        let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(&temp)?;
+        file.write_all(contents)?;
+        if let Some(meta) = &original {
+            file.set_permissions(meta.permissions())?;
+            // Only root (or the owner, for the group) may chown; keep going otherwise.
+            // std's safe `fchown` needs Rust 1.73, above our MSRV, so call libc directly.
+            // SAFETY: fchown takes no pointers, only a descriptor, and `file` owns that
+            // descriptor and keeps it open for the call, so it can't name another file.
+            let _ = unsafe { libc::fchown(std::os::unix::io::AsRawFd::as_raw_fd(&file), meta.uid(), meta.gid()) };
+        }
+        file.sync_all()?;
+        std::fs::rename(&temp, &target)
+    })();
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e);
+    }
+
+    // Persist the rename itself; not every platform supports syncing a folder
+    if let Ok(d) = std::fs::File::open(&dir) {
+        let _ = d.sync_all();
+    }
+
+    Ok(backup)
+}
+
+/// Unified diff between two versions of `file`, with git-style a/ b/ headers.
+pub fn unified_diff(file: &Path, old: &str, new: &str) -> String {
+    let name = file.display().to_string();
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", name), &format!("b/{}", name))
+        .to_string()
+}
+
+/// Print a unified diff with the usual red/green colouring.
+pub fn print_diff(diff: &str) {
+    use colored::*;
+
+    for line in diff.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            println!("{}", line.bold());
+        } else if line.starts_with('+') {
+            println!("{}", line.green());
+        } else if line.starts_with('-') {
+            println!("{}", line.red());
+        } else if line.starts_with("@@") {
+            println!("{}", line.cyan());
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn write_atomic_replaces_contents_and_keeps_mode() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let file = dir.join("script.sh");
+        std::fs::write(&file, "old").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o750)).unwrap();
+
+        assert_eq!(write_atomic(&file, b"new", None).unwrap(), None);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "new");
+        assert_eq!(std::fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0o750);
+        // No temp files left behind
+        assert_eq!(names(dir), ["script.sh"]);
+    }
+
+    #[test]
+    fn write_atomic_backs_up_the_previous_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let file = dir.join("notes.txt");
+        std::fs::write(&file, "v1").unwrap();
+
+        let backup = write_atomic(&file, b"v2", Some(".orig")).unwrap();
+        assert_eq!(backup, Some(dir.join("notes.txt.orig")));
+        assert_eq!(std::fs::read_to_string(dir.join("notes.txt.orig")).unwrap(), "v1");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v2");
+
+        // A file that didn't exist yet has nothing to back up
+        let fresh = dir.join("fresh.txt");
+        assert_eq!(write_atomic(&fresh, b"x", Some(".orig")).unwrap(), None);
+        assert_eq!(names(dir), ["fresh.txt", "notes.txt", "notes.txt.orig"]);
+    }
+
+    #[test]
+    fn write_atomic_writes_through_symlinks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let target = dir.join("real.txt");
+        let link = dir.join("link.txt");
+        std::fs::write(&target, "old").unwrap();
+        std::os::unix::fs::symlink("real.txt", &link).unwrap();
+
+        write_atomic(&link, b"new", None).unwrap();
+        assert!(link.is_symlink());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "new");
+    }
+
+    #[test]
+    fn write_atomic_fails_cleanly_when_the_folder_is_missing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let file = dir.join("no-such-folder").join("file.txt");
+        assert!(write_atomic(&file, b"x", None).is_err());
+        assert!(names(dir).is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use ignore::{DirEntry, WalkBuilder, WalkState};
+
+/// How to walk a folder. The default visits everything, like `du` would.
+pub struct WalkOptions {
+    /// Visit hidden files and folders
+    pub hidden: bool,
+    /// Skip whatever .gitignore/.ignore files (and git's global excludes) list
+    pub respect_ignore: bool,
+    /// Never descend into `.git` folders
+    pub skip_git: bool,
+    /// `Some(0)` is just the root, `Some(1)` its children, ...
+    pub max_depth: Option<usize>,
+    /// Worker threads; 0 picks one per CPU
+    pub threads: usize,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions { hidden: true, respect_ignore: false, skip_git: false, max_depth: None, threads: 0 }
+    }
+}
+
+/// Walk `root` on a pool of work-stealing threads, calling `visit` on every
+/// entry (the root included, at depth 0) and keeping what it returns.
+///
+/// Symlinks are reported but never followed, so link loops can't trap the
+/// walk. Unreadable entries are skipped. Results come back sorted by path,
+/// which puts each folder right before its contents, so output doesn't
+/// depend on thread timing.
+pub fn walk<T, F>(root: &Path, options: &WalkOptions, visit: F) -> Vec<(PathBuf, T)>
+where
+    T: Send,
+    F: Fn(&DirEntry) -> Option<T> + Sync,
+{
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(!options.hidden)
+        .ignore(options.respect_ignore)
+        .git_ignore(options.respect_ignore)
+        .git_global(options.respect_ignore)
+        .git_exclude(options.respect_ignore)
+        .parents(options.respect_ignore)
+        .require_git(false)
+        .follow_links(false)
+        .max_depth(options.max_depth)
+        .threads(options.threads);
+    if options.skip_git {
+        builder.filter_entry(|e| e.file_name() != ".git");
+    }
+
+    let found = Mutex::new(Vec::new());
+    builder.build_parallel().run(|| {
+        let visit = &visit;
+        let found = &found;
+        Box::new(move |entry| {
+            if let Ok(entry) = entry {
+                if let Some(value) = visit(&entry) {
+                    found.lock().unwrap().push((entry.into_path(), value));
+                }
+            }
+            WalkState::Continue
+        })
+    });
+
+    let mut found = found.into_inner().unwrap();
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    found
+}
+
+/// Run `visit` over a known list of paths on `threads` threads (0 = one per
+/// CPU), for when an index already says which files to look at. Threads pull
+/// the next path as they finish, and results come back sorted like `walk`.
+pub fn map_paths<T, F>(paths: Vec<PathBuf>, threads: usize, visit: F) -> Vec<(PathBuf, T)>
+where
+    T: Send,
+    F: Fn(&Path) -> Option<T> + Sync,
+{
+    let threads = if threads == 0 {
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    } else {
+        threads
+    };
+    let next = AtomicUsize::new(0);
+    let found = Mutex::new(Vec::new());
+    std::thread::scope(|scope| {
+        for _ in 0..threads.min(paths.len()).max(1) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = paths.get(i) else { break };
+                if let Some(value) = visit(path) {
+                    found.lock().unwrap().push((path.clone(), value));
+                }
+            });
+        }
+    });
+
+    let mut found = found.into_inner().unwrap();
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    found
+}
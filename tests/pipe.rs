@@ -0,0 +1,33 @@
+use assert_cmd::Command;
+
+#[test]
+fn find_results_pipe_into_rename() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    std::fs::write(dir.path().join("sub/draft-1.txt"), "one").unwrap();
+    std::fs::write(dir.path().join("sub/draft-2.txt"), "two").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "three").unwrap();
+
+    // Piped find output is bare paths, relative to where ez runs
+    let found = Command::cargo_bin("ez").unwrap()
+        .current_dir(dir.path())
+        .args(["find", "draft", "sub"])
+        .output()
+        .unwrap();
+    assert!(found.status.success());
+    let mut paths: Vec<&str> = std::str::from_utf8(&found.stdout).unwrap().lines().collect();
+    paths.sort();
+    assert_eq!(paths, ["sub/draft-1.txt", "sub/draft-2.txt"]);
+
+    Command::cargo_bin("ez").unwrap()
+        .current_dir(dir.path())
+        .args(["rename", "draft", "final"])
+        .write_stdin(found.stdout)
+        .assert()
+        .success();
+
+    assert!(dir.path().join("sub/final-1.txt").exists());
+    assert!(dir.path().join("sub/final-2.txt").exists());
+    assert!(!dir.path().join("sub/draft-1.txt").exists());
+    assert!(dir.path().join("notes.txt").exists());
+}